@@ -1,15 +1,25 @@
 //! What does wind look like?
 
-use clap::Parser;
-use nannou::noise::{NoiseFn, OpenSimplex, Perlin, Value};
+use clap::{Parser, ValueEnum};
+use nannou::image::{Rgb as ImageRgb, RgbImage};
+use nannou::noise::{NoiseFn, OpenSimplex, Perlin, Seedable, Value, Worley};
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::attach_capture;
+use nannou_genuary_2025::common::record::{RecordArgs, Recorder};
+use nannou_genuary_2025::common::watermark::{corner_point, draw_watermark, Corner};
+use std::path::{Path, PathBuf};
+
+const FIELD_NOISE_SCALE: f64 = 0.1; // Noise units per flow field cell
+const RESPAWN_ATTEMPTS: usize = 8; // Edge points to try before giving up and respawning anywhere
+const DEFAULT_FIELD_HZ: f32 = 30.0;
+const ATTRACTOR_CORE: f32 = 20.0; // Inside this distance the mouse pulls at full strength
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Wind visualization using nannou")]
 struct Args {
-    /// Type of noise to use (perlin, simplex, value)
-    #[arg(short, long, default_value = "perlin")]
-    noise_type: String,
+    /// Type of noise to use
+    #[arg(short, long, value_enum, default_value_t = NoiseType::Perlin, ignore_case = true)]
+    noise_type: NoiseType,
 
     /// Window width
     #[arg(long, default_value_t = 800)]
@@ -26,30 +36,228 @@ struct Args {
     /// Maximum number of particles
     #[arg(short, long, default_value_t = 1000)]
     max_particles: usize,
+
+    /// Render the flow field as a hue-by-angle PNG and exit instead of opening a window
+    #[arg(long)]
+    field_png: Option<PathBuf>,
+
+    /// Side length in pixels of the `--field-png` image
+    #[arg(long, default_value_t = 1024)]
+    field_resolution: u32,
+
+    /// Direction of the prevailing wind in degrees (0 = east, 90 = north)
+    #[arg(long, default_value_t = 0.0)]
+    prevailing: f64,
+
+    /// How strongly the flow is pulled toward the prevailing direction (0 = none, 1 = all)
+    #[arg(long, default_value_t = 0.0)]
+    prevailing_strength: f64,
+
+    /// Sample the noise on a torus so the field matches up where particles wrap around
+    #[arg(long)]
+    seamless: bool,
+
+    /// Give each particle a hue from where it spawned so the trails form colored streams
+    #[arg(long)]
+    streamline_color: bool,
+
+    /// Color each trail by the direction its particle is heading, round the hue wheel
+    #[arg(long)]
+    color_by_direction: bool,
+
+    /// Push particles across the flow instead of along it, swirling into curl lines
+    #[arg(long, alias = "invert")]
+    curl: bool,
+
+    /// Seed for the noise generator, which otherwise always makes the same field
+    #[arg(long)]
+    noise_seed: Option<u32>,
+
+    /// How strongly the flow field pushes the particles each step
+    #[arg(long, default_value_t = 0.5)]
+    force_scale: f32,
+
+    /// Where dead particles come back: anywhere, or on the edge the wind is blowing in from
+    #[arg(long, value_enum, default_value_t = RespawnMode::Random)]
+    respawn_mode: RespawnMode,
+
+    /// How many times a second to resample the flow field; particles see a blend of the
+    /// two latest samples in between
+    #[arg(long, default_value_t = DEFAULT_FIELD_HZ)]
+    field_hz: f32,
+
+    /// Distance from the cursor, in pixels, within which a held mouse button pulls (left)
+    /// or pushes (right) the particles
+    #[arg(long, default_value_t = 150.0)]
+    attract_radius: f32,
+
+    /// How hard a held mouse button pulls or pushes the particles nearest the cursor
+    #[arg(long, default_value_t = 2.0)]
+    attract_strength: f32,
+
+    /// Fastest a particle can move, in pixels per step
+    #[arg(long, default_value_t = 2.0)]
+    max_speed: f32,
+
+    /// Fraction of its velocity a particle keeps each step (1 = no damping)
+    #[arg(long, default_value_t = 1.0)]
+    damping: f32,
+
+    /// Draw the flow field as faint arrows under the particles
+    #[arg(long)]
+    show_field: bool,
+
+    /// Show the noise settings in the top-right corner
+    #[arg(long)]
+    legend: bool,
+
+    /// Kinds of particle: 2 adds a second kind, in a contrasting color, that moves across
+    /// the flow where the first moves along it (or along it under `--curl`)
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=2))]
+    species: u8,
+
+    /// Fraction of particles that are the second kind under `--species 2`
+    #[arg(long, default_value_t = 0.5)]
+    species_ratio: f32,
+
+    /// Layers of noise summed into the field, each finer and fainter than the last
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    octaves: u32,
+
+    /// How much finer each octave is than the one before
+    #[arg(long, default_value_t = 2.0)]
+    lacunarity: f64,
+
+    /// How much fainter each octave is than the one before
+    #[arg(long, default_value_t = 0.5)]
+    persistence: f64,
+
+    #[command(flatten)]
+    record: RecordArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum NoiseType {
+    Perlin,
+    Simplex,
+    Value,
+    Worley,
+}
+
+impl NoiseType {
+    fn name(self) -> &'static str {
+        match self {
+            NoiseType::Perlin => "perlin",
+            NoiseType::Simplex => "simplex",
+            NoiseType::Value => "value",
+            NoiseType::Worley => "worley",
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RespawnMode {
+    Random,
+    Edge,
+}
+
+/// How a particle responds to the flow field.
+#[derive(Clone, Copy, PartialEq)]
+enum Species {
+    Flow, // Pushed along the field
+    Curl, // Pushed a quarter turn across it
 }
 
 enum NoiseGenerator {
     Perlin(Perlin),
     Simplex(OpenSimplex),
     Value(Value),
+    Worley(Worley), // Cellular, so the wind gathers into patches
 }
 
 impl NoiseGenerator {
+    fn new(noise_type: NoiseType, seed: Option<u32>) -> Self {
+        let generator = match noise_type {
+            NoiseType::Perlin => NoiseGenerator::Perlin(Perlin::new()),
+            NoiseType::Simplex => NoiseGenerator::Simplex(OpenSimplex::new()),
+            NoiseType::Value => NoiseGenerator::Value(Value::new()),
+            NoiseType::Worley => NoiseGenerator::Worley(Worley::new()),
+        };
+        match seed {
+            Some(seed) => generator.set_seed(seed),
+            None => generator,
+        }
+    }
+
+    fn set_seed(self, seed: u32) -> Self {
+        match self {
+            NoiseGenerator::Perlin(noise) => NoiseGenerator::Perlin(noise.set_seed(seed)),
+            NoiseGenerator::Simplex(noise) => NoiseGenerator::Simplex(noise.set_seed(seed)),
+            NoiseGenerator::Value(noise) => NoiseGenerator::Value(noise.set_seed(seed)),
+            NoiseGenerator::Worley(noise) => NoiseGenerator::Worley(noise.set_seed(seed)),
+        }
+    }
+
     fn get_noise(&self, x: f64, y: f64, z: f64) -> f64 {
         match self {
             NoiseGenerator::Perlin(noise) => noise.get([x, y, z]),
             NoiseGenerator::Simplex(noise) => noise.get([x, y, z]),
             NoiseGenerator::Value(noise) => noise.get([x, y, z]),
+            NoiseGenerator::Worley(noise) => noise.get([x, y, z]),
+        }
+    }
+
+    fn get_noise_4d(&self, point: [f64; 4]) -> f64 {
+        match self {
+            NoiseGenerator::Perlin(noise) => noise.get(point),
+            NoiseGenerator::Simplex(noise) => noise.get(point),
+            NoiseGenerator::Value(noise) => noise.get(point),
+            NoiseGenerator::Worley(noise) => noise.get(point),
         }
     }
+
+    /// Fractional Brownian motion: `octaves` layers of noise, each `lacunarity` times finer
+    /// and `persistence` times fainter than the last, scaled back to the range of a single
+    /// layer. One octave is exactly `get_noise`.
+    fn fbm(&self, x: f64, y: f64, z: f64, octaves: u32, lacunarity: f64, persistence: f64) -> f64 {
+        layered(octaves, lacunarity, persistence, |frequency| {
+            self.get_noise(x * frequency, y * frequency, z * frequency)
+        })
+    }
+
+    /// `fbm` in four dimensions, for the seamless torus.
+    fn fbm_4d(&self, point: [f64; 4], octaves: u32, lacunarity: f64, persistence: f64) -> f64 {
+        layered(octaves, lacunarity, persistence, |frequency| {
+            self.get_noise_4d(point.map(|p| p * frequency))
+        })
+    }
+}
+
+/// Sums `sample` at growing frequencies and shrinking amplitudes, divided by the total
+/// amplitude.
+fn layered(octaves: u32, lacunarity: f64, persistence: f64, sample: impl Fn(f64) -> f64) -> f64 {
+    let (mut frequency, mut amplitude) = (1.0, 1.0);
+    let (mut sum, mut total) = (0.0, 0.0);
+    for _ in 0..octaves.max(1) {
+        sum += sample(frequency) * amplitude;
+        total += amplitude;
+        frequency *= lacunarity;
+        amplitude *= persistence;
+    }
+    sum / total
 }
 
 struct Model {
     particles: Vec<Particle>,
     noise: NoiseGenerator,
-    flow_field: Vec<Vec2>,
+    flow_field: Vec<Vec2>, // What the particles follow: a blend of the two samples
+    previous_field: Vec<Vec2>, // Sampled at `last_sample_time`
+    next_field: Vec<Vec2>, // Sampled one resample interval later
+    field_blend: f32,      // How far `flow_field` is from `previous_field` to `next_field`
+    last_sample_time: f32,
     grid_size: usize,
     cell_size: f32,
+    recorder: Option<Recorder>,
     args: Args,
 }
 
@@ -58,48 +266,87 @@ struct Particle {
     velocity: Vec2,
     prev_position: Point2,
     life: f32,
+    color: Srgb, // Trail color; its alpha comes from `life`
+    kind: Species,
 }
 
 impl Particle {
-    fn new(x: f32, y: f32) -> Self {
+    fn new(x: f32, y: f32, args: &Args) -> Self {
+        let primary = if args.curl {
+            Species::Curl
+        } else {
+            Species::Flow
+        };
+        let second = args.species > 1 && random_f32() < args.species_ratio;
+        let kind = match (primary, second) {
+            (Species::Flow, true) => Species::Curl,
+            (Species::Curl, true) => Species::Flow,
+            (kind, false) => kind,
+        };
+
+        let color = if args.streamline_color {
+            // Hue runs around the center so neighbouring streams get similar colors. The
+            // second kind takes the opposite hue.
+            let hue = (y.atan2(x) / TAU + if second { 0.5 } else { 0.0 }).rem_euclid(1.0);
+            hsl(hue, 0.7, 0.45).into()
+        } else if second {
+            rgb(0.8, 0.15, 0.2)
+        } else {
+            rgb(0.0, 0.0, 0.0)
+        };
         Particle {
             position: pt2(x, y),
             velocity: vec2(0.0, 0.0),
             prev_position: pt2(x, y),
             life: random_range(0.5, 1.0),
+            color,
+            kind,
         }
     }
 
+    /// Heading of the particle as a hue from 0 to 1, east being red.
+    fn hue(&self) -> f32 {
+        (self.velocity.y.atan2(self.velocity.x) / TAU).rem_euclid(1.0)
+    }
+
     fn update(
         &mut self,
         rect: Rect,
         flow_field: &[Vec2],
         grid_size: usize,
         cell_size: f32,
-        life_reduction: f32,
+        args: &Args,
+        attractor: Option<(Point2, f32)>,
     ) {
         self.prev_position = self.position;
 
-        // Get grid position
-        let grid_x = ((self.position.x - rect.left()) / cell_size).floor() as usize;
-        let grid_y = ((self.position.y - rect.bottom()) / cell_size).floor() as usize;
-
-        // Ensure we're within bounds
-        if grid_x < grid_size && grid_y < grid_size {
-            let index = grid_y * grid_size + grid_x;
+        if let Some(index) = field_index(self.position, rect, grid_size, cell_size) {
             if index < flow_field.len() {
-                // Apply force from flow field
-                let force = flow_field[index];
-                self.velocity += force * 0.5;
+                // Apply force from flow field, turned a quarter for curl lines
+                let force = match self.kind {
+                    Species::Curl => flow_field[index].perp(),
+                    Species::Flow => flow_field[index],
+                };
+                self.velocity += force * args.force_scale;
+            }
+        }
+
+        // Pull toward (or push away from) the mouse, falling off with the square of distance
+        if let Some((target, strength)) = attractor {
+            let offset = target - self.position;
+            let distance = offset.length();
+            if distance > 0.0 && distance < args.attract_radius {
+                let falloff = (ATTRACTOR_CORE / distance).powi(2).min(1.0);
+                self.velocity += offset / distance * strength * falloff;
             }
         }
 
         // Update position
-        self.velocity = self.velocity.clamp_length_max(2.0);
+        self.velocity = (self.velocity * args.damping).clamp_length_max(args.max_speed);
         self.position += self.velocity;
 
         // Reduce life
-        self.life -= life_reduction;
+        self.life -= args.life_reduction;
 
         // Wrap around edges
         if self.position.x < rect.left() {
@@ -121,16 +368,136 @@ impl Particle {
     }
 }
 
+/// Index of the flow field cell under `position`, if it's on the grid.
+fn field_index(position: Point2, rect: Rect, grid_size: usize, cell_size: f32) -> Option<usize> {
+    let grid_x = ((position.x - rect.left()) / cell_size).floor() as usize;
+    let grid_y = ((position.y - rect.bottom()) / cell_size).floor() as usize;
+    if grid_x < grid_size && grid_y < grid_size {
+        Some(grid_y * grid_size + grid_x)
+    } else {
+        None
+    }
+}
+
+/// Samples the noise into a `cols * rows` field of unit vectors, stored row by row from the
+/// bottom. `step` is the size of one sample in flow field cells.
+fn build_flow_field(
+    noise: &NoiseGenerator,
+    args: &Args,
+    cols: usize,
+    rows: usize,
+    step: f64,
+    time: f64,
+) -> Vec<Vec2> {
+    let prevailing = args.prevailing.to_radians();
+    let tau = core::f64::consts::TAU;
+    let mut flow_field = Vec::with_capacity(cols * rows);
+    for y in 0..rows {
+        for x in 0..cols {
+            let value = if args.seamless {
+                // Each axis goes once round its own circle, sized so the noise is sampled
+                // at the same density as the flat field. Time slides along the first axis,
+                // which moves the torus without breaking its periodicity.
+                let x_angle = x as f64 / cols as f64 * tau;
+                let y_angle = y as f64 / rows as f64 * tau;
+                let x_radius = cols as f64 * step * FIELD_NOISE_SCALE / tau;
+                let y_radius = rows as f64 * step * FIELD_NOISE_SCALE / tau;
+                noise.fbm_4d(
+                    [
+                        x_radius * x_angle.cos() + time * 0.1,
+                        x_radius * x_angle.sin(),
+                        y_radius * y_angle.cos(),
+                        y_radius * y_angle.sin(),
+                    ],
+                    args.octaves,
+                    args.lacunarity,
+                    args.persistence,
+                )
+            } else {
+                noise.fbm(
+                    x as f64 * step * FIELD_NOISE_SCALE,
+                    y as f64 * step * FIELD_NOISE_SCALE,
+                    time * 0.1,
+                    args.octaves,
+                    args.lacunarity,
+                    args.persistence,
+                )
+            };
+            let angle = value * core::f64::consts::PI * 2.0;
+            let angle = bias_angle(angle, prevailing, args.prevailing_strength);
+
+            flow_field.push(vec2(angle.cos() as f32, angle.sin() as f32));
+        }
+    }
+    flow_field
+}
+
+/// Turns `angle` toward `target` along the shorter way round by `strength` (0 to 1).
+fn bias_angle(angle: f64, target: f64, strength: f64) -> f64 {
+    let tau = core::f64::consts::TAU;
+    let difference =
+        (target - angle + core::f64::consts::PI).rem_euclid(tau) - core::f64::consts::PI;
+    angle + difference * strength.clamp(0.0, 1.0)
+}
+
 fn main() {
-    nannou::app(model).update(update).run();
+    let args = Args::parse();
+    if let Some(path) = &args.field_png {
+        export_field_png(&args, path);
+        return;
+    }
+
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+/// Writes the flow field at `t = 0` as an image where hue encodes the wind direction.
+fn export_field_png(args: &Args, path: &Path) {
+    let grid_size = 32;
+    let resolution = args.field_resolution.max(1);
+    let noise = NoiseGenerator::new(args.noise_type, args.noise_seed);
+    let step = grid_size as f64 / resolution as f64;
+    let field = build_flow_field(
+        &noise,
+        args,
+        resolution as usize,
+        resolution as usize,
+        step,
+        0.0,
+    );
+
+    let image = RgbImage::from_fn(resolution, resolution, |x, y| {
+        // Image rows run top-down while the field runs bottom-up.
+        let v = field[((resolution - 1 - y) * resolution + x) as usize];
+        let hue = (v.y.atan2(v.x) / TAU).rem_euclid(1.0);
+        let color: Srgb = hsv(hue, 0.8, 0.9).into();
+        let color: Srgb<u8> = color.into_format();
+        ImageRgb([color.red, color.green, color.blue])
+    });
+
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("field");
+    let file_name = format!(
+        "{}_{}_scale{}.png",
+        stem,
+        args.noise_type.name(),
+        FIELD_NOISE_SCALE
+    );
+    let output = path.with_file_name(file_name);
+    match image.save(&output) {
+        Ok(()) => println!("Wrote {}", output.display()),
+        Err(e) => eprintln!("Failed to write {}: {}", output.display(), e),
+    }
 }
 
 fn model(app: &App) -> Model {
-    let args = Args::parse();
-    let _window = app
-        .new_window()
-        .size(args.width, args.height)
-        .view(view)
+    let mut args = Args::parse();
+    if args.field_hz <= 0.0 {
+        eprintln!(
+            "Warning: --field-hz must be positive ({}), using {}",
+            args.field_hz, DEFAULT_FIELD_HZ
+        );
+        args.field_hz = DEFAULT_FIELD_HZ;
+    }
+    let window = attach_capture(app.new_window().size(args.width, args.height).view(view))
         .build()
         .unwrap();
 
@@ -138,24 +505,18 @@ fn model(app: &App) -> Model {
     let cell_size = args.width as f32 / grid_size as f32;
 
     // Initialize noise generator based on argument
-    let noise = match args.noise_type.to_lowercase().as_str() {
-        "simplex" => NoiseGenerator::Simplex(OpenSimplex::new()),
-        "value" => NoiseGenerator::Value(Value::new()),
-        _ => NoiseGenerator::Perlin(Perlin::new()),
-    };
+    let noise = NoiseGenerator::new(args.noise_type, args.noise_seed);
 
     // Initialize flow field
-    let mut flow_field = Vec::with_capacity(grid_size * grid_size);
-
-    for y in 0..grid_size {
-        for x in 0..grid_size {
-            let angle = noise.get_noise(x as f64 * 0.1, y as f64 * 0.1, app.time as f64 * 0.1)
-                * core::f64::consts::PI
-                * 2.0;
-
-            flow_field.push(vec2(angle.cos() as f32, angle.sin() as f32));
-        }
-    }
+    let previous_field = build_flow_field(&noise, &args, grid_size, grid_size, 1.0, 0.0);
+    let next_field = build_flow_field(
+        &noise,
+        &args,
+        grid_size,
+        grid_size,
+        1.0,
+        1.0 / args.field_hz as f64,
+    );
 
     // Create initial particles
     let particles = (0..args.max_particles)
@@ -163,6 +524,7 @@ fn model(app: &App) -> Model {
             Particle::new(
                 random_range(-(args.width as f32) / 2.0, args.width as f32 / 2.0),
                 random_range(-(args.height as f32) / 2.0, args.height as f32 / 2.0),
+                &args,
             )
         })
         .collect();
@@ -170,30 +532,98 @@ fn model(app: &App) -> Model {
     Model {
         particles,
         noise,
-        flow_field,
+        flow_field: previous_field.clone(),
+        previous_field,
+        next_field,
+        field_blend: 0.0,
+        last_sample_time: 0.0,
         grid_size,
         cell_size,
+        recorder: Recorder::from_args(&args.record, window),
         args,
     }
 }
 
+/// Resamples the noise only once per `--field-hz` interval. Sampling ahead to the end of the
+/// interval lets the particles glide between the two fields instead of jumping each resample.
+fn update_flow_field(model: &mut Model, time: f32) {
+    let interval = 1.0 / model.args.field_hz;
+    let elapsed = ((time - model.last_sample_time) / interval).floor();
+    if elapsed >= 1.0 {
+        model.last_sample_time += elapsed * interval;
+        let sample = |time: f32| {
+            build_flow_field(
+                &model.noise,
+                &model.args,
+                model.grid_size,
+                model.grid_size,
+                1.0,
+                time as f64,
+            )
+        };
+        // Usually the old next field is exactly the new previous one, unless frames were slow.
+        model.previous_field = if elapsed == 1.0 {
+            std::mem::take(&mut model.next_field)
+        } else {
+            sample(model.last_sample_time)
+        };
+        model.next_field = sample(model.last_sample_time + interval);
+    }
+
+    model.field_blend = ((time - model.last_sample_time) / interval).clamp(0.0, 1.0);
+    model.flow_field = model
+        .previous_field
+        .iter()
+        .zip(&model.next_field)
+        .map(|(previous, next)| previous.lerp(*next, model.field_blend).normalize_or_zero())
+        .collect();
+}
+
+/// A random point on an edge where the wind is blowing into the window, or `None` if a few
+/// tries all land where it's blowing out.
+fn upwind_edge_point(model: &Model, rect: Rect) -> Option<Point2> {
+    // Just inside the edge, so the point falls in the outermost row or column of cells.
+    let inner = rect.pad(0.5);
+    (0..RESPAWN_ATTEMPTS).find_map(|_| {
+        let (position, inward) = match random_range(0, 4) {
+            0 => (
+                pt2(inner.left(), random_range(inner.bottom(), inner.top())),
+                vec2(1.0, 0.0),
+            ),
+            1 => (
+                pt2(inner.right(), random_range(inner.bottom(), inner.top())),
+                vec2(-1.0, 0.0),
+            ),
+            2 => (
+                pt2(random_range(inner.left(), inner.right()), inner.bottom()),
+                vec2(0.0, 1.0),
+            ),
+            _ => (
+                pt2(random_range(inner.left(), inner.right()), inner.top()),
+                vec2(0.0, -1.0),
+            ),
+        };
+        let index = field_index(position, rect, model.grid_size, model.cell_size)?;
+        let flow = model.flow_field.get(index)?;
+        (flow.dot(inward) > 0.0).then_some(position)
+    })
+}
+
 fn update(app: &App, model: &mut Model, _update: Update) {
+    // A recording steps the clock a frame at a time so it plays back at an even speed.
+    let time = model.recorder.as_ref().map_or(app.time, Recorder::time);
+
     // Update flow field
-    model.flow_field.clear();
-    for y in 0..model.grid_size {
-        for x in 0..model.grid_size {
-            let angle =
-                model
-                    .noise
-                    .get_noise(x as f64 * 0.1, y as f64 * 0.1, app.time as f64 * 0.1)
-                    * core::f64::consts::PI
-                    * 2.0;
-
-            model
-                .flow_field
-                .push(vec2(angle.cos() as f32, angle.sin() as f32));
-        }
-    }
+    update_flow_field(model, time);
+
+    // A held mouse button pulls (left) or pushes (right); letting go leaves just the flow
+    let attractor = if app.mouse.buttons.left().is_down() {
+        Some((app.mouse.position(), model.args.attract_strength))
+    } else if app.mouse.buttons.right().is_down() {
+        Some((app.mouse.position(), -model.args.attract_strength))
+    } else {
+        None
+    };
 
     // Update particles
     let rect = app.window_rect();
@@ -203,23 +633,44 @@ fn update(app: &App, model: &mut Model, _update: Update) {
             &model.flow_field,
             model.grid_size,
             model.cell_size,
-            model.args.life_reduction,
+            &model.args,
+            attractor,
         );
     }
 
     // Remove dead particles and add new ones
     model.particles.retain(|p| p.life > 0.0);
     while model.particles.len() < model.args.max_particles {
-        model.particles.push(Particle::new(
-            random_range(
-                -(model.args.width as f32) / 2.0,
-                model.args.width as f32 / 2.0,
-            ),
-            random_range(
-                -(model.args.height as f32) / 2.0,
-                model.args.height as f32 / 2.0,
-            ),
-        ));
+        let position = match model.args.respawn_mode {
+            RespawnMode::Edge => upwind_edge_point(model, rect),
+            RespawnMode::Random => None,
+        }
+        .unwrap_or_else(|| {
+            pt2(
+                random_range(
+                    -(model.args.width as f32) / 2.0,
+                    model.args.width as f32 / 2.0,
+                ),
+                random_range(
+                    -(model.args.height as f32) / 2.0,
+                    model.args.height as f32 / 2.0,
+                ),
+            )
+        });
+        model
+            .particles
+            .push(Particle::new(position.x, position.y, &model.args));
+    }
+
+    if let Some(recorder) = &mut model.recorder {
+        recorder.capture(app);
+    }
+}
+
+/// Lets any frames still being written by `--record-frames` finish before exiting.
+fn exit(app: &App, model: Model) {
+    if let Some(recorder) = &model.recorder {
+        recorder.finish(app);
     }
 }
 
@@ -230,23 +681,81 @@ fn view(app: &App, model: &Model, frame: Frame) {
     draw.background().color(LINEN);
 
     // Draw date in bottom left
-    draw.text("1.18")
-        .color(rgba(0.0, 0.0, 0.0, 0.5))
-        .font_size(24)
-        .align_text_bottom()
-        .x_y(
-            -(model.args.width as f32) / 2.0 + 40.0,
-            -(model.args.height as f32) / 2.0 + 110.0,
-        );
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        "1.18",
+        Corner::BottomLeft,
+        None,
+        LINEN.into_format(),
+    );
+
+    if model.args.legend {
+        draw_legend(model, &draw, app.window_rect());
+    }
+
+    if model.args.show_field {
+        draw_field(model, &draw, app.window_rect());
+    }
 
     // Draw particles as lines from previous position
     for particle in &model.particles {
+        let color: Srgb = if model.args.color_by_direction {
+            hsl(particle.hue(), 0.7, 0.45).into()
+        } else {
+            particle.color
+        };
         draw.line()
             .start(particle.prev_position)
             .end(particle.position)
-            .color(rgba(0.0, 0.0, 0.0, particle.life))
+            .color(rgba(color.red, color.green, color.blue, particle.life))
             .stroke_weight(2.0);
     }
 
     draw.to_frame(app, &frame).unwrap();
 }
+
+/// An arrow along the flow at the center of each grid cell, laid out from the same corner
+/// `Particle::update` uses to find a particle's cell.
+fn draw_field(model: &Model, draw: &Draw, rect: Rect) {
+    let half_length = model.cell_size / 4.0;
+    for (index, direction) in model.flow_field.iter().enumerate() {
+        let grid_x = index % model.grid_size;
+        let grid_y = index / model.grid_size;
+        let center = pt2(
+            rect.left() + (grid_x as f32 + 0.5) * model.cell_size,
+            rect.bottom() + (grid_y as f32 + 0.5) * model.cell_size,
+        );
+        draw.arrow()
+            .start(center - *direction * half_length)
+            .end(center + *direction * half_length)
+            .head_length(half_length / 2.0)
+            .head_width(half_length / 3.0)
+            .stroke_weight(1.0)
+            .color(rgba(0.0, 0.0, 0.0, 0.25));
+    }
+}
+
+/// The settings that shape the field, opposite the date so they don't overlap.
+fn draw_legend(model: &Model, draw: &Draw, rect: Rect) {
+    let args = &model.args;
+    let seed = args
+        .noise_seed
+        .map_or_else(|| "default".to_string(), |seed| seed.to_string());
+    let legend = format!(
+        "noise: {} (seed {})\ngrid: {}x{}\nparticles: {}\nlife reduction: {}",
+        args.noise_type.name(),
+        seed,
+        model.grid_size,
+        model.grid_size,
+        model.particles.len(),
+        args.life_reduction,
+    );
+    draw.text(&legend)
+        .color(rgba(0.0, 0.0, 0.0, 0.5))
+        .font_size(14)
+        .w(240.0)
+        .right_justify()
+        .align_text_top()
+        .xy(corner_point(rect, Corner::TopRight, vec2(140.0, 80.0)));
+}
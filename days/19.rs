@@ -1,7 +1,23 @@
 //! Op art.
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use nannou::image::{Rgba as ImageRgba, RgbaImage};
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::attach_capture;
+use nannou_genuary_2025::common::color::parse_hex_color;
+use nannou_genuary_2025::common::lerp::lerp;
+use nannou_genuary_2025::common::pulse::{Pulse, PulseArgs};
+use nannou_genuary_2025::common::record::{RecordArgs, Recorder};
+use nannou_genuary_2025::common::svg::{write_svg, Polyline};
+use nannou_genuary_2025::common::watermark::{draw_watermark, Corner};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+const VIGNETTE_RINGS: u32 = 48; // Rings the vignette's falloff is built from
+const ZIGZAG_SEGMENTS: usize = 20; // Zigs and zags from the center to the edge of each line
+const SINE_SEGMENTS: usize = 200; // Enough that a wavy line reads as a curve
+const RING_PHASE: f32 = 0.5; // Each ring's turn against the one outside it, in line spacings
+const SWEEP_GAP: u32 = 8; // Pixels between the tiles of the `--sweep` contact sheet
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Wind visualization using nannou")]
@@ -28,6 +44,178 @@ struct Args {
     /// Factor of how zig-zaggy each line is
     #[arg(long, default_value_t = 5.0)]
     zig_zagginess: f32,
+
+    /// Whether the lines swing side to side in sharp zig-zags or smooth waves
+    #[arg(long, value_enum, default_value_t = LineShape::Zigzag)]
+    line_shape: LineShape,
+
+    /// Color each zig-zag segment by its apex so the colors pulse as the lines rotate
+    #[arg(long)]
+    apex_color: bool,
+
+    /// Color at the center of each line, which shades along it into `--color-end`
+    #[arg(long, default_value = "#1d3557", value_parser = parse_hex_color)]
+    color_start: Srgb,
+
+    /// Color at the outer end of each line
+    #[arg(long, default_value = "#e63946", value_parser = parse_hex_color)]
+    color_end: Srgb,
+
+    /// Draw each line in plain black instead of the `--color-start` to `--color-end` shading
+    #[arg(long)]
+    monochrome: bool,
+
+    /// Fan the lines over the upper half only and reflect them into the lower half
+    #[arg(long)]
+    mirror: bool,
+
+    /// Overlay a second, slightly offset layer of lines for moiré interference
+    #[arg(long)]
+    moire: bool,
+
+    /// Number of zig-zag lines in the moiré layer (defaults to two more than `--num-lines`)
+    #[arg(long)]
+    moire_lines: Option<u32>,
+
+    /// Rotation of the moiré layer relative to the first, in degrees
+    #[arg(long, default_value_t = 1.0)]
+    moire_offset: f32,
+
+    /// Nest this many rings of lines inside one another, each a little smaller and turned
+    /// against the last
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    rings: u32,
+
+    /// Spin every other ring the opposite way
+    #[arg(long)]
+    counter_rotate: bool,
+
+    #[command(flatten)]
+    pulse: PulseArgs,
+
+    /// Darken the pattern outside a circular aperture
+    #[arg(long)]
+    vignette: bool,
+
+    /// Radius of the clear aperture in the middle of the vignette
+    #[arg(long, default_value_t = 300.0)]
+    aperture: f32,
+
+    /// Distance over which the vignette fades from clear to fully dark
+    #[arg(long, default_value_t = 120.0)]
+    vignette_feather: f32,
+
+    /// Instead of opening a window, render the pattern across a range of one parameter as
+    /// `<param>:<start>:<end>:<steps>` (param is num_lines, radius or zig_zagginess) and
+    /// tile the renders into a contact sheet
+    #[arg(long, value_parser = parse_sweep)]
+    sweep: Option<Sweep>,
+
+    /// Where `--sweep` writes its contact sheet
+    #[arg(long, default_value = "sweep.png")]
+    sweep_out: PathBuf,
+
+    /// Write the first frame's lines to this SVG file on startup, colored as on screen
+    #[arg(long)]
+    export_svg: Option<PathBuf>,
+
+    #[command(flatten)]
+    record: RecordArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LineShape {
+    Zigzag,
+    Sine,
+}
+
+impl LineShape {
+    /// Straight segments each line is drawn with.
+    fn segments(self) -> usize {
+        match self {
+            LineShape::Zigzag => ZIGZAG_SEGMENTS,
+            LineShape::Sine => SINE_SEGMENTS,
+        }
+    }
+}
+
+/// A parameter of the pattern `--sweep` can vary.
+#[derive(Clone, Copy, Debug)]
+enum SweepParam {
+    NumLines,
+    Radius,
+    ZigZagginess,
+}
+
+impl SweepParam {
+    fn name(self) -> &'static str {
+        match self {
+            SweepParam::NumLines => "num_lines",
+            SweepParam::Radius => "radius",
+            SweepParam::ZigZagginess => "zig_zagginess",
+        }
+    }
+}
+
+/// `steps` evenly spaced values of `param` from `start` to `end`, inclusive.
+#[derive(Clone, Copy, Debug)]
+struct Sweep {
+    param: SweepParam,
+    start: f32,
+    end: f32,
+    steps: u32,
+}
+
+impl Sweep {
+    fn value(&self, step: u32) -> f32 {
+        if self.steps < 2 {
+            return self.start;
+        }
+        self.start + (self.end - self.start) * step as f32 / (self.steps - 1) as f32
+    }
+}
+
+fn parse_sweep(s: &str) -> Result<Sweep, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let [param, start, end, steps] = parts[..] else {
+        return Err(format!("expected <param>:<start>:<end>:<steps>, got {}", s));
+    };
+    let param = match param.replace('-', "_").as_str() {
+        "num_lines" => SweepParam::NumLines,
+        "radius" => SweepParam::Radius,
+        "zig_zagginess" => SweepParam::ZigZagginess,
+        _ => {
+            return Err(format!(
+                "can't sweep {}, choose num_lines, radius or zig_zagginess",
+                param
+            ))
+        }
+    };
+    let number = |value: &str| {
+        value
+            .parse::<f32>()
+            .map_err(|e| format!("{}: {}", value, e))
+    };
+    let steps = steps
+        .parse::<u32>()
+        .map_err(|e| format!("{}: {}", steps, e))?;
+    if steps == 0 {
+        return Err("a sweep needs at least one step".to_string());
+    }
+    Ok(Sweep {
+        param,
+        start: number(start)?,
+        end: number(end)?,
+        steps,
+    })
+}
+
+/// A set of zig-zag lines fanned around the center.
+struct Layer {
+    num_lines: u32,
+    rotation_offset: f32, // Radians added to the model's rotation
+    radius_scale: f32,    // Fraction of the model's radius the lines reach
+    spin: f32,            // 1 to turn with the model's rotation, -1 to turn against it
 }
 
 struct Model {
@@ -35,88 +223,425 @@ struct Model {
     height: u32,
     rotation: f32,
     rotation_speed: f32,
-    num_lines: u32,
     radius: f32,
     zig_zagginess: f32,
+    line_shape: LineShape,
+    apex_color: bool,
+    gradient: Option<(Hsl, Hsl)>, // Colors at the center and outer end of each line
+    mirror: bool,
+    layers: Vec<Layer>,
+    pulse: Option<Pulse>,
+    pulse_scale: f32,             // Current radius multiplier from the pulse
+    vignette: Option<(f32, f32)>, // Aperture radius and feather width
+    recorder: Option<Recorder>,
 }
 
 fn main() {
-    nannou::app(model).update(update).run();
+    let args = Args::parse();
+    if args.sweep.is_some() {
+        nannou::app(export_sweep)
+            .update(|app, _, _| app.quit())
+            .run();
+        return;
+    }
+
+    nannou::app(model).update(update).exit(exit).run();
+}
+
+/// Renders the still pattern once per value of the sweep, through the same drawing code
+/// as the window, and tiles the renders left to right, top to bottom into a single PNG.
+/// The window is only there for its GPU device and is never shown.
+fn export_sweep(app: &App) {
+    let args = Args::parse();
+    let Some(sweep) = args.sweep else {
+        return;
+    };
+    let (width, height) = (args.width.max(1), args.height.max(1));
+    let window_id = app
+        .new_window()
+        .size(width, height)
+        .visible(false)
+        .build()
+        .unwrap();
+    let window = app.window(window_id).unwrap();
+    let device = window.device();
+
+    let texture = wgpu::TextureBuilder::new()
+        .size([width, height])
+        .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+        .sample_count(window.msaa_samples())
+        .format(wgpu::TextureFormat::Rgba8UnormSrgb)
+        .build(device);
+    let mut renderer = nannou::draw::RendererBuilder::new()
+        .build_from_texture_descriptor(device, texture.descriptor());
+    let capturer = wgpu::TextureCapturer::default();
+    let (tile_sender, tiles) = mpsc::channel();
+    let draw = Draw::new();
+
+    for step in 0..sweep.steps {
+        let value = sweep.value(step);
+        let mut tile = pattern_model(&args);
+        match sweep.param {
+            SweepParam::NumLines => {
+                tile.layers = pattern_layers(&args, value.round().max(1.0) as u32);
+            }
+            SweepParam::Radius => tile.radius = value,
+            SweepParam::ZigZagginess => tile.zig_zagginess = value,
+        }
+        draw.reset();
+        draw_scene(&tile, &draw);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("sweep"),
+        });
+        renderer.render_to_texture(device, &mut encoder, &draw, &texture);
+        let snapshot = capturer.capture(device, &mut encoder, &texture);
+        window.queue().submit(Some(encoder.finish()));
+
+        let tile_sender = tile_sender.clone();
+        let read = snapshot.read(move |result| match result {
+            Ok(image) => {
+                let _ = tile_sender.send((step, image.to_owned()));
+            }
+            Err(e) => eprintln!("Failed to read back tile {} ({:?})", step + 1, e),
+        });
+        if let Err(e) = read {
+            eprintln!("Failed to read back tile {} ({:?})", step + 1, e);
+        }
+        println!("Tile {}: {} = {}", step + 1, sweep.param.name(), value);
+    }
+    if let Err(e) = capturer.await_active_snapshots(device) {
+        eprintln!("Failed to finish reading back the tiles ({:?})", e);
+    }
+    drop(tile_sender);
+
+    let cols = (sweep.steps as f32).sqrt().ceil() as u32;
+    let rows = sweep.steps.div_ceil(cols);
+    let mut sheet = RgbaImage::from_pixel(
+        cols * (width + SWEEP_GAP) - SWEEP_GAP,
+        rows * (height + SWEEP_GAP) - SWEEP_GAP,
+        ImageRgba([255, 255, 255, 255]),
+    );
+    for (step, tile) in tiles.try_iter() {
+        let left = (step % cols) * (width + SWEEP_GAP);
+        let top = (step / cols) * (height + SWEEP_GAP);
+        for (x, y, pixel) in tile.enumerate_pixels() {
+            sheet.put_pixel(left + x, top + y, *pixel);
+        }
+    }
+
+    match sheet.save(&args.sweep_out) {
+        Ok(()) => println!("Saved contact sheet to {}", args.sweep_out.display()),
+        Err(e) => eprintln!(
+            "Failed to save contact sheet to {}: {}",
+            args.sweep_out.display(),
+            e
+        ),
+    }
 }
 
 fn model(app: &App) -> Model {
     let args = Args::parse();
-    app.new_window()
-        .size(args.width, args.height)
-        .view(view)
+    let window = attach_capture(app.new_window().size(args.width, args.height).view(view))
         .build()
         .unwrap();
 
+    let mut model = pattern_model(&args);
+    model.recorder = Recorder::from_args(&args.record, window);
+
+    if let Some(path) = &args.export_svg {
+        export_svg(&model, path);
+    }
+    model
+}
+
+/// The pattern as the arguments describe it, still and unrecorded.
+fn pattern_model(args: &Args) -> Model {
     Model {
         width: args.width,
         height: args.height,
         rotation: 0.0, // Initial rotation state, not an arg
         rotation_speed: args.rotation_speed,
-        num_lines: args.num_lines,
         radius: args.radius,
         zig_zagginess: args.zig_zagginess,
+        line_shape: args.line_shape,
+        apex_color: args.apex_color,
+        gradient: (!args.monochrome).then(|| (args.color_start.into(), args.color_end.into())),
+        mirror: args.mirror,
+        layers: pattern_layers(args, args.num_lines),
+        pulse: Pulse::from_args(&args.pulse),
+        pulse_scale: 1.0,
+        vignette: args
+            .vignette
+            .then_some((args.aperture, args.vignette_feather.max(1.0))),
+        recorder: None,
+    }
+}
+
+/// The layers of every ring, with `num_lines` lines in the main layer of each.
+fn pattern_layers(args: &Args, num_lines: u32) -> Vec<Layer> {
+    // Line counts and turns of the layers making up each ring
+    let mut ring = vec![(num_lines, 0.0)];
+    if args.moire {
+        ring.push((
+            args.moire_lines.unwrap_or(num_lines + 2),
+            args.moire_offset.to_radians(),
+        ));
     }
+
+    // The outermost ring is the full pattern; each one inside is a step smaller.
+    let span = if args.mirror { PI } else { TAU };
+    let mut layers = Vec::new();
+    for k in 0..args.rings {
+        let spin = if args.counter_rotate && !k.is_multiple_of(2) {
+            -1.0
+        } else {
+            1.0
+        };
+        for &(num_lines, rotation_offset) in &ring {
+            layers.push(Layer {
+                num_lines,
+                rotation_offset: rotation_offset + k as f32 * RING_PHASE * span / num_lines as f32,
+                radius_scale: (args.rings - k) as f32 / args.rings as f32,
+                spin,
+            });
+        }
+    }
+    layers
 }
 
-fn update(_app: &App, model: &mut Model, _update: Update) {
+/// Writes every line of the pattern as it stands, still and unpulsed at startup.
+fn export_svg(model: &Model, path: &Path) {
+    let mut lines = Vec::new();
+    for layer in &model.layers {
+        let (angle_step, paths) = layer_lines(model, layer);
+        for points in paths {
+            match segment_colors(model, points.len() - 1, angle_step) {
+                // A path per segment, the way the window draws a colored line
+                Some(colors) => lines.extend(points.windows(2).zip(colors).map(
+                    |(segment, color)| Polyline {
+                        points: segment.to_vec(),
+                        stroke: Rgb::from(color).into_format(),
+                        stroke_width: 2.0,
+                    },
+                )),
+                None => lines.push(Polyline {
+                    points,
+                    stroke: BLACK,
+                    stroke_width: 2.0,
+                }),
+            }
+        }
+    }
+    match write_svg(path, model.width, model.height, LINEN, &lines) {
+        Ok(()) => println!("Saved SVG to {}", path.display()),
+        Err(e) => eprintln!("Failed to save SVG to {}: {}", path.display(), e),
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
     model.rotation += model.rotation_speed;
+    if let Some(pulse) = &model.pulse {
+        // A recording steps the clock a frame at a time so it plays back at an even speed.
+        let time = model.recorder.as_ref().map_or(app.time, Recorder::time);
+        model.pulse_scale = pulse.scale(time);
+    }
+
+    if let Some(recorder) = &mut model.recorder {
+        recorder.capture(app);
+    }
+}
+
+/// Lets any frames still being written by `--record-frames` finish before exiting.
+fn exit(app: &App, model: Model) {
+    if let Some(recorder) = &model.recorder {
+        recorder.finish(app);
+    }
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
+    draw_scene(model, &draw);
+
+    // Day watermark (bottom-left)
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        "1.19",
+        Corner::BottomLeft,
+        None,
+        LINEN.into_format(),
+    );
+
+    draw.to_frame(app, &frame).unwrap();
+}
+
+/// Everything but the watermark, so `--sweep` tiles match the window.
+fn draw_scene(model: &Model, draw: &Draw) {
     draw.background().color(LINEN);
 
-    let center = pt2(0.0, 0.0);
-    let angle_step = TAU / model.num_lines as f32;
+    for layer in &model.layers {
+        draw_layer(model, draw, layer);
+    }
+
+    if let Some((aperture, feather)) = model.vignette {
+        draw_vignette(model, draw, aperture, feather);
+    }
+}
+
+fn draw_layer(model: &Model, draw: &Draw, layer: &Layer) {
+    let (angle_step, lines) = layer_lines(model, layer);
+    for points in lines {
+        draw_zigzag(model, draw, points, angle_step);
+    }
+}
 
-    for i in 0..model.num_lines {
-        let angle = i as f32 * angle_step + model.rotation;
-        let mut points = Vec::new();
+/// The angle between neighbouring lines of the layer, and the points of each line as they
+/// are now, reflections included.
+fn layer_lines(model: &Model, layer: &Layer) -> (f32, Vec<Vec<Point2>>) {
+    // Mirrored lines only fan over the upper half, wrapping round as they rotate.
+    let span = if model.mirror { PI } else { TAU };
+    let angle_step = span / layer.num_lines as f32;
+    let effective_radius = model.radius * layer.radius_scale * model.pulse_scale;
+    let rotation = model.rotation * layer.spin + layer.rotation_offset;
 
-        // Create zigzag points from center to edge
-        let segments = 20;
-        let segment_length = model.radius / segments as f32;
-        let zigzag_width = angle_step * model.zig_zagginess; // Width of zigzag
+    let mut lines = Vec::new();
+    for i in 0..layer.num_lines {
+        let angle = (i as f32 * angle_step + rotation).rem_euclid(span);
+        let points = zigzag_points(
+            angle,
+            angle_step,
+            effective_radius,
+            model.zig_zagginess,
+            model.line_shape,
+        );
+
+        if model.mirror {
+            lines.push(points.iter().map(|p| pt2(p.x, -p.y)).collect());
+        }
+        lines.push(points);
+    }
+    (angle_step, lines)
+}
+
+/// Points of one zig-zag line from the center out to `radius` at `angle`, swinging to
+/// either side by less the further out it gets.
+fn zigzag_points(
+    angle: f32,
+    angle_step: f32,
+    radius: f32,
+    zig_zagginess: f32,
+    shape: LineShape,
+) -> Vec<Point2> {
+    let center = pt2(0.0, 0.0);
+    let segments = shape.segments();
+    let segment_length = radius / segments as f32;
+    let zigzag_width = angle_step * zig_zagginess; // Width of zigzag
 
-        for j in 0..=segments {
+    (0..=segments)
+        .map(|j| {
             let dist = j as f32 * segment_length;
-            let offset = if j % 2 == 0 {
+            let offset = lateral_offset(shape, j, segments, zigzag_width, zig_zagginess);
+            let point_angle = angle + (offset * (1.0 - dist / radius));
+            pt2(
+                center.x + dist * point_angle.cos(),
+                center.y + dist * point_angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Angle the `j`th of `segments` points swings off the line's center by, before tapering.
+/// The wave makes `cycles` full swings from center to edge, starting on the same side as
+/// the zig-zag.
+fn lateral_offset(
+    shape: LineShape,
+    j: usize,
+    segments: usize,
+    zigzag_width: f32,
+    cycles: f32,
+) -> f32 {
+    match shape {
+        LineShape::Zigzag => {
+            if j.is_multiple_of(2) {
                 zigzag_width
             } else {
                 -zigzag_width
-            };
-            let point_angle = angle + (offset * (1.0 - dist / model.radius));
-
-            let x = center.x + dist * point_angle.cos();
-            let y = center.y + dist * point_angle.sin();
-            points.push(pt2(x, y));
+            }
         }
+        LineShape::Sine => zigzag_width * (TAU * cycles * j as f32 / segments as f32).cos(),
+    }
+}
 
-        // Draw the zigzag line
-        draw.polyline()
-            .stroke_weight(2.0)
-            .points(points)
-            .color(BLACK);
+/// Rings of rising opacity from the edge of the aperture out past the window's corners,
+/// fully dark from `feather` beyond the aperture onwards.
+fn draw_vignette(model: &Model, draw: &Draw, aperture: f32, feather: f32) {
+    let corner = (model.width as f32).hypot(model.height as f32) / 2.0;
+    let outer = corner.max(aperture + feather);
+    let ring_width = (outer - aperture) / VIGNETTE_RINGS as f32;
+    for i in 0..VIGNETTE_RINGS {
+        let radius = aperture + (i as f32 + 0.5) * ring_width;
+        let t = ((radius - aperture) / feather).min(1.0);
+        let alpha = t * t * (3.0 - 2.0 * t); // Smoothstep
+        draw.ellipse()
+            .radius(radius)
+            .no_fill()
+            .stroke_weight(ring_width + 0.5) // Overlap a little so no seams show
+            .stroke(rgba(0.0, 0.0, 0.0, alpha));
+    }
+}
 
-        // Day watermark (bottom-left)
-        watermark(model, &draw);
+fn draw_zigzag(model: &Model, draw: &Draw, points: Vec<Point2>, angle_step: f32) {
+    match segment_colors(model, points.len() - 1, angle_step) {
+        Some(colors) => {
+            for (segment, color) in points.windows(2).zip(colors) {
+                draw.line()
+                    .start(segment[0])
+                    .end(segment[1])
+                    .stroke_weight(2.0)
+                    .caps_round()
+                    .color(color);
+            }
+        }
+        None => {
+            draw.polyline()
+                .stroke_weight(2.0)
+                .points(points)
+                .color(BLACK);
+        }
     }
+}
 
-    draw.to_frame(app, &frame).unwrap();
+/// The color of each of a line's `segments`, or `None` when it's drawn in plain black.
+fn segment_colors(model: &Model, segments: usize, angle_step: f32) -> Option<Vec<Hsl>> {
+    if model.apex_color {
+        // One full hue cycle each time the pattern rotates by one line's worth of angle.
+        let phase = model.rotation / angle_step;
+        let colors = (0..segments)
+            .map(|j| {
+                hsl(
+                    (j as f32 / segments as f32 + phase).rem_euclid(1.0),
+                    0.7,
+                    0.45,
+                )
+            })
+            .collect();
+        return Some(colors);
+    }
+    let (start, end) = model.gradient?;
+    let colors = (0..segments)
+        .map(|j| mix_hsl(start, end, (j as f32 + 0.5) / segments as f32))
+        .collect();
+    Some(colors)
 }
 
-fn watermark(model: &Model, draw: &Draw) {
-    draw.text("1.19")
-        .color(rgba(0.0, 0.0, 0.0, 0.5))
-        .font_size(24)
-        .align_text_bottom()
-        .x_y(
-            -(model.width as f32) / 2.0 + 40.0,
-            -(model.height as f32) / 2.0 + 110.0,
-        );
+/// The color `t` of the way from `a` to `b`, turning the shorter way round the hue wheel.
+fn mix_hsl(a: Hsl, b: Hsl, t: f32) -> Hsl {
+    let a_hue = a.hue.to_positive_degrees();
+    let turn = (b.hue.to_positive_degrees() - a_hue + 180.0).rem_euclid(360.0) - 180.0;
+    Hsl::new(
+        a_hue + turn * t,
+        lerp(a.saturation, b.saturation, t),
+        lerp(a.lightness, b.lightness, t),
+    )
 }
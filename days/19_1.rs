@@ -1,8 +1,13 @@
 //! Op art.
 //! With zoom.
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::attach_capture;
+use nannou_genuary_2025::common::pulse::{Pulse, PulseArgs};
+use nannou_genuary_2025::common::watermark::{draw_watermark, Corner};
+
+const WRAP_FADE: f32 = 0.25; // Share of each `--zoom-mode wrap` cycle spent crossfading
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Wind visualization using nannou")]
@@ -22,6 +27,19 @@ struct Args {
     #[arg(long, default_value_t = 0.01)]
     zoom_speed: f32,
 
+    /// What happens as the zoom keeps going: grow forever, bounce between `--zoom-min` and
+    /// `--zoom-max`, or fade back to `--zoom-min` on reaching `--zoom-max`
+    #[arg(long, value_enum, default_value_t = ZoomMode::Grow)]
+    zoom_mode: ZoomMode,
+
+    /// Smallest zoom under `--zoom-mode pingpong` or `wrap`
+    #[arg(long, default_value_t = 1.0)]
+    zoom_min: f32,
+
+    /// Largest zoom under `--zoom-mode pingpong` or `wrap`
+    #[arg(long, default_value_t = 3.0)]
+    zoom_max: f32,
+
     /// Number of zig-zag lines
     #[arg(long, default_value_t = 72)]
     num_lines: u32,
@@ -33,18 +51,31 @@ struct Args {
     /// Factor of how zig-zaggy each line is
     #[arg(long, default_value_t = 5.0)]
     zig_zagginess: f32,
+
+    #[command(flatten)]
+    pulse: PulseArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ZoomMode {
+    Grow,
+    Pingpong,
+    Wrap,
 }
 
 struct Model {
-    width: u32,
-    height: u32,
     rotation: f32,
     rotation_speed: f32,
     zoom: f32,
     zoom_speed: f32,
+    zoom_mode: ZoomMode,
+    zoom_range: (f32, f32), // Bounds for `pingpong` and `wrap`
+    zoom_direction: f32,    // 1 while zooming in, -1 while zooming out
     num_lines: u32,
     radius: f32,
     zig_zagginess: f32,
+    pulse: Option<Pulse>,
+    pulse_scale: f32, // Current zoom multiplier from the pulse
 }
 
 fn main() {
@@ -52,38 +83,99 @@ fn main() {
 }
 
 fn model(app: &App) -> Model {
-    let args = Args::parse();
-    app.new_window()
-        .size(args.width, args.height)
-        .view(view)
+    let mut args = Args::parse();
+    if args.zoom_max <= args.zoom_min && !matches!(args.zoom_mode, ZoomMode::Grow) {
+        eprintln!(
+            "Warning: --zoom-max ({}) must be above --zoom-min ({}), zooming without a limit",
+            args.zoom_max, args.zoom_min
+        );
+        args.zoom_mode = ZoomMode::Grow;
+    }
+    attach_capture(app.new_window().size(args.width, args.height).view(view))
         .build()
         .unwrap();
 
     Model {
-        width: args.width,
-        height: args.height,
         rotation: 0.0,
         rotation_speed: args.rotation_speed,
-        zoom: 1.0, // Initial zoom state
+        zoom: match args.zoom_mode {
+            ZoomMode::Grow => 1.0, // Initial zoom state
+            ZoomMode::Pingpong | ZoomMode::Wrap => 1.0_f32.clamp(args.zoom_min, args.zoom_max),
+        },
         zoom_speed: args.zoom_speed,
+        zoom_mode: args.zoom_mode,
+        zoom_range: (args.zoom_min, args.zoom_max),
+        zoom_direction: 1.0,
         num_lines: args.num_lines,
         radius: args.radius,
         zig_zagginess: args.zig_zagginess,
+        pulse: Pulse::from_args(&args.pulse),
+        pulse_scale: 1.0,
     }
 }
 
-fn update(_app: &App, model: &mut Model, _update: Update) {
+fn update(app: &App, model: &mut Model, _update: Update) {
     model.rotation += model.rotation_speed;
-    model.zoom += model.zoom_speed;
+    let (min, max) = model.zoom_range;
+    match model.zoom_mode {
+        ZoomMode::Grow => model.zoom += model.zoom_speed,
+        ZoomMode::Pingpong => {
+            model.zoom += model.zoom_speed * model.zoom_direction;
+            // Reflect off whichever bound was crossed.
+            if model.zoom > max {
+                model.zoom = 2.0 * max - model.zoom;
+                model.zoom_direction = -1.0;
+            } else if model.zoom < min {
+                model.zoom = 2.0 * min - model.zoom;
+                model.zoom_direction = 1.0;
+            }
+        }
+        ZoomMode::Wrap => {
+            model.zoom += model.zoom_speed;
+            // The faded-in copy is already this far along, so carry on from it.
+            if model.zoom >= max {
+                model.zoom -= (max - min) * (1.0 - WRAP_FADE);
+            }
+        }
+    }
+    if let Some(pulse) = &model.pulse {
+        model.pulse_scale = pulse.scale(app.time);
+    }
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
     draw.background().color(LINEN);
 
+    // Towards the end of each wrap a fresh copy from the start of the cycle fades in over
+    // the old one, so that the jump back lands on what's already showing.
+    let (min, max) = model.zoom_range;
+    let fade_start = max - (max - min) * WRAP_FADE;
+    if matches!(model.zoom_mode, ZoomMode::Wrap) && model.zoom > fade_start {
+        let t = (model.zoom - fade_start) / (max - fade_start);
+        draw_pattern(&draw, model, model.zoom, 1.0 - t);
+        draw_pattern(&draw, model, min + model.zoom - fade_start, t);
+    } else {
+        draw_pattern(&draw, model, model.zoom, 1.0);
+    }
+
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        "1.19",
+        Corner::BottomLeft,
+        None,
+        LINEN.into_format(),
+    );
+    draw.to_frame(app, &frame).unwrap();
+}
+
+/// The lines at `zoom` (before the pulse), faded to `alpha`.
+fn draw_pattern(draw: &Draw, model: &Model, zoom: f32, alpha: f32) {
     let center = pt2(0.0, 0.0);
     let angle_step = TAU / model.num_lines as f32;
-    let effective_radius = model.radius * model.zoom;
+    let zoom = zoom * model.pulse_scale;
+    let effective_radius = model.radius * zoom;
 
     for i in 0..model.num_lines {
         let angle = i as f32 * angle_step + model.rotation;
@@ -95,7 +187,7 @@ fn view(app: &App, model: &Model, frame: Frame) {
 
         for j in 0..=segments {
             let dist = j as f32 * segment_length;
-            let base_dist = dist / model.zoom; // Unscaled distance for zigzag calculation
+            let base_dist = dist / zoom; // Unscaled distance for zigzag calculation
             let offset = if j % 2 == 0 {
                 zigzag_width
             } else {
@@ -113,20 +205,6 @@ fn view(app: &App, model: &Model, frame: Frame) {
         draw.polyline()
             .stroke_weight(2.0)
             .points(points)
-            .color(BLACK);
+            .color(rgba(0.0, 0.0, 0.0, alpha));
     }
-
-    watermark(model, &draw);
-    draw.to_frame(app, &frame).unwrap();
-}
-
-fn watermark(model: &Model, draw: &Draw) {
-    draw.text("1.19")
-        .color(rgba(0.0, 0.0, 0.0, 0.5))
-        .font_size(24)
-        .align_text_bottom()
-        .x_y(
-            -(model.width as f32) / 2.0 + 40.0,
-            -(model.height as f32) / 2.0 + 110.0,
-        );
 }
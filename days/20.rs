@@ -1,47 +1,484 @@
+use clap::{Parser, ValueEnum};
 use nannou::ease;
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::attach_capture;
+use nannou_genuary_2025::common::color::parse_hex_color;
+use nannou_genuary_2025::common::lerp::lerp;
+use nannou_genuary_2025::common::watermark::{draw_watermark, Corner};
 use rand::seq::SliceRandom;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
+use std::path::{Path, PathBuf};
 
 const OS_WINDOW_WIDTH: u32 = 800;
 const OS_WINDOW_HEIGHT: u32 = 800;
 const BASE_SIZE: f32 = 60.0;
-const ISO_ANGLE_RADIANS: f32 = 0.0;
+const MAX_ISO_ANGLE: f32 = 0.6; // Furthest `--iso-angle` turns the footprint's edges either way
 const BUILDING_ANIMATION_SPEED: f32 = 0.5;
 const PHI: f32 = 1.618033988749894848204586834365638118;
 const BUILDING_HEIGHT: f32 = BASE_SIZE * PHI;
 const NUM_WINDOW_ROWS: u32 = 3;
 const NUM_WINDOW_COLS: u32 = 4;
 const WINDOW_SIZE: f32 = 5.0;
-const WINDOW_ISO_STAGGER_FACTOR: f32 = 15.0; // Would be nice to set in radians but oh well
+const WINDOW_SILL_HEIGHT: f32 = 7.5; // Gap between a face's bottom edge and its lowest windows
 
 const WINDOW_ANIMATION_DURATION: f32 = 3.0;
 const WINDOW_ANIMATION_DELAY: f32 = 0.2; // Delay between windows appearing
+const WINDOW_ANIMATION_START: f32 = 1.0; // Time the first window starts appearing
+
+const ELEVATOR_TRAVEL_TIME: f32 = 2.0; // Seconds for an elevator to ride from bottom to top
+const ELEVATOR_MIN_PERIOD: f32 = 4.0;
+const ELEVATOR_MAX_PERIOD: f32 = 9.0;
+
+const FLICKER_MIN_GAP: f32 = 2.0; // Shortest a flickering window stays on or off, in seconds
+const FLICKER_MAX_GAP: f32 = 12.0;
+
+const SKYLINE_MIN_DEPTH: f32 = 0.2; // Depth 0 is the main building, 1 the far horizon
+const SKYLINE_RISE: f32 = 180.0; // How far up the screen the farthest buildings stand
+const SKYLINE_FAR_SCALE: f32 = 0.35; // Size of a building at depth 1 relative to depth 0
+const SKYLINE_HAZE: f32 = 0.75; // Default share of a building's color the haze takes at the horizon
+const SKYLINE_SCROLL_SPEED: f32 = 30.0; // Pixels per second of the nearest row under --parallax
+
+const CITY_SPACING: f32 = 1.5; // Distance between neighbouring lots, in footprint half-widths
+
+const SHADOW_LENGTH: f32 = 1.2; // Shadow length as a multiple of the building's height
+const SHADOW_FORESHORTENING: f32 = 0.5; // Vertical squash of ground directions in the iso view
+
+const MAST_HEIGHT: f32 = 35.0;
+const MAST_MAX_SWAY_DEGREES: f32 = 6.0; // Furthest the mast leans either way in the wind
+const FLAG_WIDTH: f32 = 18.0;
+const FLAG_HEIGHT: f32 = 11.0;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Isometric building using nannou")]
+struct Args {
+    /// Radians to turn the footprint's edges from 45° towards the horizontal; about 0.26
+    /// gives the classic 30° isometric look
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    iso_angle: f32,
+
+    /// Number of elevator lights riding up the window columns
+    #[arg(long, default_value_t = 0)]
+    elevators: usize,
+
+    /// Squeeze or stretch the whole build-up to finish in this many seconds
+    #[arg(long)]
+    timelapse: Option<f32>,
+
+    /// Image mask lighting only the windows under its bright pixels. It must be
+    /// 2*cols x rows pixels: the left face's windows, then the right face's, top row first.
+    #[arg(long)]
+    pattern: Option<PathBuf>,
+
+    /// Color theme for the faces and windows of the building
+    #[arg(long, value_enum, default_value_t = Material::Plain)]
+    material: Material,
+
+    /// Cast the building's shadow onto the ground
+    #[arg(long)]
+    cast_shadows: bool,
+
+    /// Direction the light comes from in degrees (0 = east, 90 = north)
+    #[arg(long, default_value_t = 135.0)]
+    light_angle: f32,
+
+    /// Opacity of the left face (defaults to the material's)
+    #[arg(long)]
+    left_alpha: Option<f32>,
+
+    /// Opacity of the right face (defaults to the material's)
+    #[arg(long)]
+    right_alpha: Option<f32>,
+
+    /// Opacity of the top face (defaults to the material's)
+    #[arg(long)]
+    top_alpha: Option<f32>,
+
+    /// Rows of buildings in the city; the building with the windows stands at the front
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    rows: u32,
+
+    /// Columns of buildings in the city
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    cols: u32,
+
+    /// Seed for everything random, so a run can be repeated exactly
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of background buildings forming a skyline behind the main one
+    #[arg(long, default_value_t = 0)]
+    skyline: usize,
+
+    /// Scroll the skyline sideways, nearer buildings faster than far ones
+    #[arg(long)]
+    parallax: bool,
+
+    /// Color distant skyline buildings fade into, as `#rrggbb` (defaults to the background)
+    #[arg(long, value_parser = parse_hex_color)]
+    haze_color: Option<Rgb>,
+
+    /// How much of a building's color the haze takes over at the horizon, from 0 to 1
+    #[arg(long, default_value_t = SKYLINE_HAZE)]
+    haze_strength: f32,
+
+    /// Detail to put on the roof once the building is finished
+    #[arg(long, value_enum)]
+    rooftop: Option<RooftopKind>,
+
+    /// Full back-and-forth sways of the rooftop detail per second
+    #[arg(long, default_value_t = 0.4)]
+    sway_rate: f32,
+
+    /// Blinks of the antenna light per second
+    #[arg(long, default_value_t = 1.0)]
+    blink_rate: f32,
+
+    /// Render the whole animation to numbered PNGs in this directory at a fixed frame
+    /// rate, then exit
+    #[arg(long)]
+    render_sequence: Option<PathBuf>,
+
+    /// Frames per second of `--render-sequence`
+    #[arg(long, default_value_t = 30)]
+    render_fps: u32,
+
+    /// Once each window has appeared, switch its light on and off now and then as if
+    /// someone were home
+    #[arg(long)]
+    flicker: bool,
+
+    /// Leave the day watermark off
+    #[arg(long)]
+    no_watermark: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum RooftopKind {
+    Antenna,
+    Flag,
+}
+
+/// A mast standing in the middle of the roof, swaying in the wind.
+struct Rooftop {
+    kind: RooftopKind,
+    sway_rate: f32,
+    blink_rate: f32,
+}
+
+impl Rooftop {
+    fn draw(&self, draw: &Draw, top: [Point2; 4], time: f32) {
+        let base = top.iter().fold(Vec2::ZERO, |sum, v| sum + *v) / top.len() as f32;
+        let sway = (MAST_MAX_SWAY_DEGREES * (time * self.sway_rate * TAU).sin()).to_radians();
+        let up = vec2(-sway.sin(), sway.cos());
+        let tip = base + up * MAST_HEIGHT;
+        draw.line()
+            .start(base)
+            .end(tip)
+            .weight(2.0)
+            .color(rgba(0.0, 0.0, 0.0, 0.8));
+
+        match self.kind {
+            RooftopKind::Antenna => {
+                if (time * self.blink_rate).fract() < 0.5 {
+                    draw.ellipse()
+                        .xy(tip)
+                        .radius(3.0)
+                        .color(rgba(0.9, 0.1, 0.1, 1.0));
+                }
+            }
+            RooftopKind::Flag => {
+                // The free edge ripples a little behind the mast's sway.
+                let flutter = (time * self.sway_rate * TAU * 2.0 - 1.0).sin() * 3.0;
+                let across = vec2(up.y, -up.x);
+                let hoist = tip - up * FLAG_HEIGHT;
+                draw.polygon()
+                    .points([
+                        tip,
+                        tip + across * FLAG_WIDTH + up * flutter,
+                        hoist + across * FLAG_WIDTH + up * flutter,
+                        hoist,
+                    ])
+                    .color(rgba(0.8, 0.15, 0.15, 0.9));
+            }
+        }
+    }
+}
+
+/// The atmosphere distant buildings fade into.
+#[derive(Clone, Copy)]
+struct Haze {
+    color: Rgb,
+    strength: f32, // Share of the color the haze takes over at depth 1
+}
+
+/// A background building, drawn as a scaled-down and hazier copy of the main one.
+struct SkylineBuilding {
+    x: f32,
+    depth: f32,     // 0 at the main building, 1 at the horizon
+    height: f32,    // Multiple of the main building's height
+    iso_angle: f32, // Same as the main building's
+}
+
+impl SkylineBuilding {
+    fn new(rng: &mut impl Rng, iso_angle: f32) -> Self {
+        let half_width = OS_WINDOW_WIDTH as f32 / 2.0;
+        SkylineBuilding {
+            x: rng.gen_range(-half_width..half_width),
+            depth: rng.gen_range(SKYLINE_MIN_DEPTH..1.0),
+            height: rng.gen_range(0.6..1.8),
+            iso_angle,
+        }
+    }
+
+    /// Draws the building grown to `progress` of its height, scrolled by `scroll` pixels
+    /// at the nearest depth.
+    fn draw(
+        &self,
+        draw: &Draw,
+        theme: &Theme,
+        haze: Haze,
+        progress: f32,
+        scroll: f32,
+        shadow: Option<f32>,
+    ) {
+        let scale = 1.0 + (SKYLINE_FAR_SCALE - 1.0) * self.depth;
+        // Wrap with some margin so buildings slide fully off one side before reappearing.
+        let span = OS_WINDOW_WIDTH as f32 + 2.0 * BASE_SIZE;
+        let x = (self.x + scroll * (1.0 - self.depth) + span / 2.0).rem_euclid(span) - span / 2.0;
+        let placed = draw
+            .translate(vec3(x, self.depth * SKYLINE_RISE, 0.0))
+            .scale(scale);
+
+        let building = Building::new(pt2(0.0, 0.0), progress * self.height, self.iso_angle);
+        if let Some(light_angle) = shadow {
+            building.draw_shadow(&placed, light_angle);
+        }
+        building.draw(&placed, &theme.hazed(haze, self.depth));
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Material {
+    Plain,
+    Glass,
+    Concrete,
+    Neon,
+}
+
+/// Face and window colors a `Material` resolves to.
+struct Theme {
+    left: Rgba,
+    right: Rgba,
+    top: Rgba,
+    window_lit: Rgb,
+    window_unlit: Rgb,
+    window_edge: Option<Rgb>, // Outline drawn around lit windows
+}
+
+impl Theme {
+    /// Every color blended toward the haze with distance. Faces also become more opaque,
+    /// so a far building reads as a block of haze rather than showing through.
+    fn hazed(&self, haze: Haze, depth: f32) -> Theme {
+        let t = (haze.strength * depth).clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let blend = |c: Rgb| {
+            rgb(
+                mix(c.red, haze.color.red),
+                mix(c.green, haze.color.green),
+                mix(c.blue, haze.color.blue),
+            )
+        };
+        let face = |c: Rgba| {
+            let color = blend(rgb(c.red, c.green, c.blue));
+            rgba(color.red, color.green, color.blue, mix(c.alpha, 1.0))
+        };
+        Theme {
+            left: face(self.left),
+            right: face(self.right),
+            top: face(self.top),
+            window_lit: blend(self.window_lit),
+            window_unlit: blend(self.window_unlit),
+            window_edge: self.window_edge.map(blend),
+        }
+    }
+}
+
+impl Material {
+    fn theme(self) -> Theme {
+        match self {
+            Material::Plain => Theme {
+                left: rgba(0.0, 0.0, 0.0, 0.4),
+                right: rgba(0.0, 0.0, 0.0, 0.6),
+                top: rgba(0.0, 0.0, 0.0, 0.8),
+                window_lit: LINEN.into_format(),
+                window_unlit: DIMGRAY.into_format(),
+                window_edge: None,
+            },
+            Material::Glass => Theme {
+                left: rgba(0.3, 0.5, 0.7, 0.35),
+                right: rgba(0.2, 0.35, 0.55, 0.5),
+                top: rgba(0.15, 0.25, 0.4, 0.7),
+                window_lit: rgb(0.85, 0.95, 1.0),
+                window_unlit: rgb(0.35, 0.45, 0.55),
+                window_edge: None,
+            },
+            Material::Concrete => Theme {
+                left: rgba(0.6, 0.59, 0.57, 1.0),
+                right: rgba(0.45, 0.44, 0.42, 1.0),
+                top: rgba(0.35, 0.34, 0.33, 1.0),
+                window_lit: rgb(1.0, 0.9, 0.7),
+                window_unlit: rgb(0.25, 0.25, 0.25),
+                window_edge: None,
+            },
+            Material::Neon => Theme {
+                left: rgba(0.12, 0.08, 0.18, 1.0),
+                right: rgba(0.08, 0.05, 0.12, 1.0),
+                top: rgba(0.05, 0.03, 0.08, 1.0),
+                window_lit: rgb(0.3, 0.05, 0.3),
+                window_unlit: rgb(0.1, 0.08, 0.12),
+                window_edge: Some(rgb(1.0, 0.2, 0.9)),
+            },
+        }
+    }
+}
+
+/// Which windows on each face are lit, loaded from an image mask.
+struct LightPattern {
+    lit: Vec<bool>, // Row-major, `2 * NUM_WINDOW_COLS` wide with the left face first
+}
+
+impl LightPattern {
+    fn load(path: &Path) -> Result<Self, String> {
+        let image = nannou::image::open(path)
+            .map_err(|e| e.to_string())?
+            .to_luma8();
+        let expected = (NUM_WINDOW_COLS * 2, NUM_WINDOW_ROWS);
+        if image.dimensions() != expected {
+            return Err(format!(
+                "expected a {}x{} image, got {}x{}",
+                expected.0,
+                expected.1,
+                image.width(),
+                image.height()
+            ));
+        }
+        Ok(LightPattern {
+            lit: image.pixels().map(|p| p[0] > 127).collect(),
+        })
+    }
+
+    fn is_lit(&self, side: Side, row: usize, col: usize) -> bool {
+        let x = match side {
+            Side::Left => col,
+            Side::Right => col + NUM_WINDOW_COLS as usize,
+        };
+        // Window rows count up from the bottom, image rows down from the top.
+        let y = NUM_WINDOW_ROWS as usize - 1 - row;
+        self.lit[y * NUM_WINDOW_COLS as usize * 2 + x]
+    }
+}
+
+/// Speeds and durations of the build-up animation.
+struct AnimationTiming {
+    building_speed: f32,
+    window_start: f32,
+    window_delay: f32,
+    window_duration: f32,
+}
+
+impl AnimationTiming {
+    fn natural() -> Self {
+        AnimationTiming {
+            building_speed: BUILDING_ANIMATION_SPEED,
+            window_start: WINDOW_ANIMATION_START,
+            window_delay: WINDOW_ANIMATION_DELAY,
+            window_duration: WINDOW_ANIMATION_DURATION,
+        }
+    }
+
+    /// Time at which the last window finishes appearing.
+    fn total_duration(&self) -> f32 {
+        let building_done = 1.0 / self.building_speed;
+        let num_windows = NUM_WINDOW_ROWS * NUM_WINDOW_COLS;
+        let last_window_done =
+            self.window_start + (num_windows - 1) as f32 * self.window_delay + self.window_duration;
+        building_done.max(last_window_done)
+    }
+
+    /// Rescales every phase uniformly so the whole animation takes `seconds`.
+    fn scaled_to(self, seconds: f32) -> Self {
+        let factor = seconds / self.total_duration();
+        AnimationTiming {
+            building_speed: self.building_speed / factor,
+            window_start: self.window_start * factor,
+            window_delay: self.window_delay * factor,
+            window_duration: self.window_duration * factor,
+        }
+    }
+}
 
 struct Building {
     center: Point2,
     height: f32,
+    iso_angle: f32,
 }
 
 impl Building {
-    fn new(center: Point2, height: f32) -> Self {
-        Building { center, height }
+    fn new(center: Point2, height: f32, iso_angle: f32) -> Self {
+        Building {
+            center,
+            height,
+            iso_angle,
+        }
     }
 
-    pub fn draw(self, draw: &Draw) {
-        let mut vertices = Vec::new();
-        let ang = ISO_ANGLE_RADIANS;
+    /// Bottom face vertices: left, right, front, back.
+    fn footprint(&self) -> [Point2; 4] {
         let size = BASE_SIZE;
+        let depth = size * edge_slope(self.iso_angle);
 
         // Note that this makes vertices for two *diamonds* and not two *squares*.
-        // This naturally provides an isometric perspective. But an angle parameter is still
-        // provided in case it makes the end result look better.
+        // This naturally provides an isometric perspective, with the angle flattening the
+        // diamond while its corners stay the same distance apart side to side.
+        [
+            self.center + vec2(-size, 0.0),  // bottom left
+            self.center + vec2(size, 0.0),   // bottom right
+            self.center + vec2(0.0, -depth), // bottom front
+            self.center + vec2(0.0, depth),  // bottom back
+        ]
+    }
+
+    /// The footprint swept along the ground away from the light, drawn as the footprint,
+    /// its displaced copy and the quads joining their edges.
+    pub fn draw_shadow(&self, draw: &Draw, light_angle: f32) {
+        let away = (light_angle + 180.0).to_radians();
+        let offset =
+            vec2(away.cos(), away.sin() * SHADOW_FORESHORTENING) * self.height * SHADOW_LENGTH;
+        let color = rgb(0.82, 0.78, 0.72);
+
+        // Footprint corners in order around the diamond.
+        let [left, right, front, back] = self.footprint();
+        let outline = [left, front, right, back];
+        draw.polygon().points(outline).color(color);
+        draw.polygon()
+            .points(outline.map(|v| v + offset))
+            .color(color);
+        for i in 0..outline.len() {
+            let a = outline[i];
+            let b = outline[(i + 1) % outline.len()];
+            draw.polygon()
+                .points([a, b, b + offset, a + offset])
+                .color(color);
+        }
+    }
+
+    /// Top face vertices: left, right, front, back.
+    fn roof(&self) -> [Point2; 4] {
+        self.footprint().map(|v| v + vec2(0.0, self.height))
+    }
 
-        // Bottom face vertices
-        vertices.push(self.center + vec2(-size * ang.cos(), -size * ang.sin())); // bottom left
-        vertices.push(self.center + vec2(size * ang.cos(), -size * ang.sin())); // bottom right
-        vertices.push(self.center + vec2(0.0, -size)); // bottom front
-        vertices.push(self.center + vec2(0.0, size)); // bottom back
+    pub fn draw(self, draw: &Draw, theme: &Theme) {
+        let mut vertices = self.footprint().to_vec();
 
         // If looking from above, bottom face vertices are:
         //  3
@@ -49,10 +486,7 @@ impl Building {
         //  2
 
         // Top face vertices are simply the bottom vertices with a height offset.
-        vertices.push(vertices[0] + vec2(0.0, self.height)); // top left
-        vertices.push(vertices[1] + vec2(0.0, self.height)); // top right
-        vertices.push(vertices[2] + vec2(0.0, self.height)); // top front
-        vertices.push(vertices[3] + vec2(0.0, self.height)); // top back
+        vertices.extend(self.roof()); // top left, top right, top front, top back
 
         // If looking from above, top face vertices are:
         //  7
@@ -61,17 +495,14 @@ impl Building {
 
         // The edge created by vertices 6 and 2 faces the camera.
 
-        let right_color = rgba(0.0, 0.0, 0.0, 0.6);
         let right_vertices = vec![vertices[1], vertices[2], vertices[6], vertices[5]];
-        draw.polygon().points(right_vertices).color(right_color);
+        draw.polygon().points(right_vertices).color(theme.right);
 
-        let left_color = rgba(0.0, 0.0, 0.0, 0.4);
         let left_vertices = vec![vertices[0], vertices[2], vertices[6], vertices[4]];
-        draw.polygon().points(left_vertices).color(left_color);
+        draw.polygon().points(left_vertices).color(theme.left);
 
-        let top_color = rgba(0.0, 0.0, 0.0, 0.8);
         let top_vertices = vec![vertices[4], vertices[6], vertices[5], vertices[7]];
-        draw.polygon().points(top_vertices).color(top_color);
+        draw.polygon().points(top_vertices).color(theme.top);
     }
 }
 
@@ -79,64 +510,237 @@ struct Model {
     building_height: f32,
     building_animation_progress: f32,
     window_animation_start_times: Vec<Vec<f32>>, // Time when each window starts animating
+    timing: AnimationTiming,
+    elevators: Vec<Elevator>,
+    windows: Windows,
+    theme: Theme,
+    cast_shadows: bool,
+    light_angle: f32,
+    skyline: Vec<SkylineBuilding>, // Farthest first
+    city: Vec<(Point2, f32)>,      // Center and height multiple of each other lot, farthest first
+    main_center: Point2,           // Where the building with the windows stands, at the front
+    iso_angle: f32,
+    haze: Haze,
+    parallax: bool,
+    rooftop: Option<Rooftop>,
+    time: f32,       // Animation clock: the app's, or a fixed step per frame when rendering
+    start_time: f32, // App time the build-up last started, moved on by replaying with R
+    render: Option<RenderSequence>,
+    watermark: bool,
+    window: WindowId,
+}
+
+/// Where and how fast `--render-sequence` writes its frames.
+struct RenderSequence {
+    dir: PathBuf,
+    fps: u32,
+    frame: u64,
+}
+
+/// The light in one window going on and off on its own timer.
+struct Flicker {
+    lit: bool,
+    first_gap: f32, // How long the light stays on once the window has appeared
+    next_toggle: f32,
+    rng: rand::rngs::StdRng, // Each window's own, so they don't switch in step
+}
+
+impl Flicker {
+    /// A flicker whose timings all come from `seed`, so the same seed always gives the
+    /// same schedule however the frames fall.
+    fn new(seed: u64) -> Self {
+        let mut flicker = Flicker {
+            lit: true,
+            first_gap: 0.0,
+            next_toggle: 0.0,
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+        };
+        flicker.first_gap = flicker.gap();
+        flicker
+    }
+
+    /// Holds the light on until the window has finished appearing at `settled`, then
+    /// toggles it after random gaps.
+    fn update(&mut self, time: f32, settled: f32) {
+        if time < settled {
+            self.lit = true;
+            self.next_toggle = settled + self.first_gap;
+            return;
+        }
+        while time >= self.next_toggle {
+            self.lit = !self.lit;
+            self.next_toggle += self.gap();
+        }
+    }
+
+    fn gap(&mut self) -> f32 {
+        self.rng.gen_range(FLICKER_MIN_GAP..FLICKER_MAX_GAP)
+    }
+}
+
+/// One of the building's two visible faces.
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// A light that periodically rides up a single column of windows.
+struct Elevator {
+    side: Side,
+    col: usize,
+    period: f32,
+    offset: f32,
+    level: Option<f32>, // Current row position while riding, `None` while idle
+}
+
+impl Elevator {
+    fn new(rng: &mut impl Rng) -> Self {
+        let side = if rng.gen_bool(0.5) {
+            Side::Left
+        } else {
+            Side::Right
+        };
+        let period = rng.gen_range(ELEVATOR_MIN_PERIOD..ELEVATOR_MAX_PERIOD);
+        Elevator {
+            side,
+            col: rng.gen_range(0..NUM_WINDOW_COLS as usize),
+            period,
+            offset: rng.gen_range(0.0..period),
+            level: None,
+        }
+    }
+
+    fn update(&mut self, app_time: f32) {
+        let t = (app_time + self.offset) % self.period;
+        self.level = if t < ELEVATOR_TRAVEL_TIME {
+            // Start below the bottom row and finish above the top one so the light enters
+            // and leaves the column smoothly.
+            let progress = t / ELEVATOR_TRAVEL_TIME;
+            Some(progress * (NUM_WINDOW_ROWS as f32 + 1.0) - 1.0)
+        } else {
+            None
+        };
+    }
+
+    /// How brightly this elevator lights the given window, from 0.0 to 1.0.
+    fn glow(&self, side: Side, row: usize, col: usize) -> f32 {
+        match self.level {
+            Some(level) if self.side == side && self.col == col => {
+                (1.0 - (row as f32 - level).abs()).max(0.0)
+            }
+            _ => 0.0,
+        }
+    }
 }
 
 struct Window {
     row: usize,
     col: usize,
-    side: String,
+    side: Side,
+    lit: bool,                // Whether the pattern lights it at all
+    flicker: Option<Flicker>, // Switches a lit window off now and then under `--flicker`
+    center: Vec2,
     pub vertices: Vec<Vec2>,
     pub scale: f32, // Current scale of the window
 }
 
 impl Window {
-    fn new(row: usize, col: usize, side: String) -> Self {
-        Window {
+    fn new(
+        row: usize,
+        col: usize,
+        side: Side,
+        lit: bool,
+        flicker: Option<Flicker>,
+        iso_angle: f32,
+    ) -> Self {
+        let mut window = Window {
             row,
             col,
             side,
+            lit,
+            flicker,
+            center: Vec2::ZERO,
             vertices: Vec::new(),
             scale: 0.0,
+        };
+        window.calculate_vertices(iso_angle);
+        window
+    }
+
+    /// Grows the window in once its turn comes and moves its flicker on.
+    fn update(&mut self, app_time: f32, start_times: &[Vec<f32>], timing: &AnimationTiming) {
+        self.calculate_scale(app_time, start_times, timing.window_duration);
+        if let Some(flicker) = &mut self.flicker {
+            let settled = start_times[self.row][self.col] + timing.window_duration;
+            flicker.update(app_time, settled);
         }
     }
 
-    pub fn draw(&mut self, draw: &Draw, app_time: f32, start_times: &Vec<Vec<f32>>) {
-        self.calculate_scale(app_time, start_times);
-        self.calculate_vertices();
-        let center = self.calculate_center();
+    pub fn draw(&self, draw: &Draw, elevators: &[Elevator], theme: &Theme) {
+        let center = self.center;
         let scaled_vertices: Vec<Vec2> = self
             .vertices
             .iter()
             .map(|v| center + (*v - center) * self.scale)
             .collect();
-        draw.polygon().points(scaled_vertices).color(LINEN);
+
+        let glow = elevators
+            .iter()
+            .map(|e| e.glow(self.side, self.row, self.col))
+            .fold(0.0, f32::max);
+        let lit = self.lit && self.flicker.as_ref().is_none_or(|f| f.lit);
+        let base = if lit {
+            theme.window_lit
+        } else {
+            theme.window_unlit
+        };
+        // A passing elevator brightens the window toward white, whatever the theme's color.
+        let color = rgb(
+            lerp(base.red, 1.0, glow),
+            lerp(base.green, 1.0, glow),
+            lerp(base.blue, 1.0, glow),
+        );
+        draw.polygon().points(scaled_vertices.clone()).color(color);
+        if let (Some(edge), true) = (theme.window_edge, lit) {
+            draw.polyline()
+                .weight(1.5)
+                .points_closed(scaled_vertices)
+                .color(edge);
+        }
     }
 
-    fn calculate_scale(&mut self, app_time: f32, start_times: &Vec<Vec<f32>>) {
+    fn calculate_scale(&mut self, app_time: f32, start_times: &[Vec<f32>], duration: f32) {
         let start_time = start_times[self.row][self.col];
-        if app_time >= start_time {
-            let progress = ((app_time - start_time) / WINDOW_ANIMATION_DURATION).min(1.0);
-            // Use bounce ease out for the scale animation
-            self.scale = ease::cubic::ease_out(progress, 0.0, 1.0, 1.0);
-        }
+        // Back to nothing before its turn, in case the build-up was replayed.
+        let progress = ((app_time - start_time) / duration).clamp(0.0, 1.0);
+        // Use bounce ease out for the scale animation
+        self.scale = ease::cubic::ease_out(progress, 0.0, 1.0, 1.0);
     }
 
-    fn calculate_vertices(&mut self) {
-        let center: Vec2 = self.calculate_center();
+    /// The window's center and its corners at full size, which stay put for the life of
+    /// the window.
+    fn calculate_vertices(&mut self, iso_angle: f32) {
+        let center: Vec2 = self.calculate_center(iso_angle);
+        self.center = center;
         let size: f32 = WINDOW_SIZE;
+        // Sloped to run parallel to the face's bottom edge.
+        let tilt = size * edge_slope(iso_angle);
         // Note: these each make *parallelograms* and not squares.
-        if self.side == String::from("left") {
-            self.vertices.push(center + vec2(-size, 2.0 * size)); // top left
-            self.vertices.push(center + vec2(-size, 0.0)); // bottom left
-            self.vertices.push(center + vec2(size, -2.0 * size)); // bottom right
-            self.vertices.push(center + vec2(size, 0.0)); // top right
-        } else {
-            self.vertices.push(center + vec2(-size, 0.0)); // top left
-            self.vertices.push(center + vec2(-size, -2.0 * size)); // bottom left
-            self.vertices.push(center + vec2(size, 0.0)); // bottom right
-            self.vertices.push(center + vec2(size, 2.0 * size));
-            // top right
-        }
+        self.vertices = match self.side {
+            Side::Left => vec![
+                center + vec2(-size, size + tilt), // top left
+                center + vec2(-size, tilt - size), // bottom left
+                center + vec2(size, -size - tilt), // bottom right
+                center + vec2(size, size - tilt),  // top right
+            ],
+            Side::Right => vec![
+                center + vec2(-size, size - tilt),  // top left
+                center + vec2(-size, -size - tilt), // bottom left
+                center + vec2(size, tilt - size),   // bottom right
+                center + vec2(size, size + tilt),   // top right
+            ],
+        };
 
         // Vertices appear like so:
         // 0 \
@@ -146,32 +750,32 @@ impl Window {
         // And mirrored for each side of the building.
     }
 
-    fn calculate_center(&mut self) -> Vec2 {
+    fn calculate_center(&self, iso_angle: f32) -> Vec2 {
         let window_spacing_horizontal = BASE_SIZE / 4.0;
         let window_spacing_vertical = BUILDING_HEIGHT / (NUM_WINDOW_ROWS as f32 + 0.8);
 
-        // Cascades the windows downwards as they approach the center of the image.
-        let iso_stagger = if self.side == String::from("left") {
-            -(self.col as f32 * WINDOW_ISO_STAGGER_FACTOR)
-        } else {
-            self.col as f32 * WINDOW_ISO_STAGGER_FACTOR
+        // Both sides are laid out outwards from the front corner where the faces meet, so
+        // the innermost columns mirror each other exactly across the shared edge.
+        let from_corner = match self.side {
+            Side::Left => NUM_WINDOW_COLS as usize - 1 - self.col,
+            Side::Right => self.col,
         };
-        let row_offset = window_spacing_vertical * (self.row as f32 + 1.0) + iso_stagger;
-        let col_offset = window_spacing_horizontal * (self.col as f32 + 1.0);
-
-        // Fudging a bit here...
-        let start_x = if self.side == String::from("left") {
-            -BASE_SIZE - 7.5
-        } else {
-            -7.5
-        };
-        let start_y = if self.side == String::from("left") {
-            0.0
-        } else {
-            -BUILDING_HEIGHT / 2.0 + 3.0
+        let offset = window_spacing_horizontal * (from_corner as f32 + 0.5);
+        let x = match self.side {
+            Side::Left => -offset,
+            Side::Right => offset,
         };
 
-        vec2(start_x + col_offset, start_y + row_offset)
+        // Cascades the windows downwards as they approach the center of the image, following
+        // the face's bottom edge up from the front corner.
+        let [left, _, front, _] = Building::new(pt2(0.0, 0.0), 0.0, iso_angle).footprint();
+        let slope = (left.y - front.y) / (front.x - left.x);
+        let edge_y = front.y + offset * slope;
+
+        vec2(
+            x,
+            edge_y + WINDOW_SILL_HEIGHT + window_spacing_vertical * (self.row as f32 + 1.0),
+        )
     }
 }
 
@@ -181,30 +785,55 @@ struct Windows {
 }
 
 impl Windows {
-    fn new() -> Self {
+    fn new(pattern: Option<&LightPattern>, flicker_seed: Option<u64>, iso_angle: f32) -> Self {
         Windows {
-            windows_left: Windows::get_windows("left"),
-            windows_right: Windows::get_windows("right"),
+            windows_left: Windows::get_windows(Side::Left, pattern, flicker_seed, iso_angle),
+            windows_right: Windows::get_windows(Side::Right, pattern, flicker_seed, iso_angle),
         }
     }
 
-    pub fn draw(&mut self, draw: &Draw, app_time: f32, start_times: &Vec<Vec<f32>>) {
-        for windows in self
-            .windows_left
+    fn iter_mut(&mut self) -> impl Iterator<Item = &mut Window> {
+        self.windows_left
             .iter_mut()
             .chain(self.windows_right.iter_mut())
+            .flatten()
+    }
+
+    fn update(&mut self, app_time: f32, start_times: &[Vec<f32>], timing: &AnimationTiming) {
+        for window in self.iter_mut() {
+            window.update(app_time, start_times, timing);
+        }
+    }
+
+    pub fn draw(&self, draw: &Draw, elevators: &[Elevator], theme: &Theme) {
+        for window in self
+            .windows_left
+            .iter()
+            .chain(self.windows_right.iter())
+            .flatten()
         {
-            for window in windows.iter_mut() {
-                window.draw(draw, app_time, start_times);
-            }
+            window.draw(draw, elevators, theme);
         }
     }
 
-    fn get_windows(side: &str) -> Vec<Vec<Window>> {
-        (0..NUM_WINDOW_ROWS as usize)
+    fn get_windows(
+        side: Side,
+        pattern: Option<&LightPattern>,
+        flicker_seed: Option<u64>,
+        iso_angle: f32,
+    ) -> Vec<Vec<Window>> {
+        let (rows, cols) = (NUM_WINDOW_ROWS as usize, NUM_WINDOW_COLS as usize);
+        (0..rows)
             .map(|i| {
-                (0..NUM_WINDOW_COLS as usize)
-                    .map(|j| Window::new(i, j, side.to_string()))
+                (0..cols)
+                    .map(|j| {
+                        let lit = pattern.is_none_or(|p| p.is_lit(side, i, j));
+                        // Every window on both faces gets a seed of its own.
+                        let index = (side as usize * rows + i) * cols + j;
+                        let flicker =
+                            flicker_seed.map(|seed| Flicker::new(seed.wrapping_add(index as u64)));
+                        Window::new(i, j, side, lit, flicker, iso_angle)
+                    })
                     .collect()
             })
             .collect()
@@ -212,16 +841,159 @@ impl Windows {
 }
 
 fn main() {
-    nannou::app(model).update(update).run();
+    nannou::app(model).update(update).exit(exit).run();
 }
 
 fn model(app: &App) -> Model {
-    app.new_window()
-        .size(OS_WINDOW_WIDTH, OS_WINDOW_HEIGHT)
-        .view(view)
-        .build()
-        .unwrap();
+    let args = Args::parse();
+    let window = attach_capture(
+        app.new_window()
+            .size(OS_WINDOW_WIDTH, OS_WINDOW_HEIGHT)
+            .view(view)
+            .key_pressed(key_pressed),
+    )
+    .build()
+    .unwrap();
+
+    let iso_angle = args.iso_angle.clamp(-MAX_ISO_ANGLE, MAX_ISO_ANGLE);
+
+    let mut rng = match args.seed {
+        Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
+        None => rand::rngs::StdRng::from_entropy(),
+    };
+
+    let timing = match args.timelapse {
+        Some(seconds) if seconds > 0.0 => AnimationTiming::natural().scaled_to(seconds),
+        _ => AnimationTiming::natural(),
+    };
+    let window_animation_start_times = window_start_times(&timing, &mut rng);
+
+    let elevators = (0..args.elevators)
+        .map(|_| Elevator::new(&mut rng))
+        .collect();
+    let flicker_seed = args.flicker.then(|| rng.gen());
+
+    let pattern = args
+        .pattern
+        .as_deref()
+        .and_then(|path| match LightPattern::load(path) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                eprintln!(
+                    "Warning: couldn't load pattern {} ({}), lighting every window",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        });
+    let windows = Windows::new(pattern.as_ref(), flicker_seed, iso_angle);
+
+    let mut skyline: Vec<SkylineBuilding> = (0..args.skyline)
+        .map(|_| SkylineBuilding::new(&mut rng, iso_angle))
+        .collect();
+    skyline.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+
+    // Lots in painter's order: the sum of the grid coordinates grows towards the viewer, and
+    // lots with the same sum sit side by side without overlapping. The last is the front lot.
+    let mut lots: Vec<(u32, u32)> = (0..args.rows)
+        .flat_map(|y| (0..args.cols).map(move |x| (x, y)))
+        .collect();
+    lots.sort_by_key(|&(x, y)| x + y);
+    let middle = iso_project(
+        (args.cols - 1) as f32 / 2.0,
+        (args.rows - 1) as f32 / 2.0,
+        iso_angle,
+    );
+    let main_lot = lots.pop().unwrap();
+    let main_center = iso_project(main_lot.0 as f32, main_lot.1 as f32, iso_angle) - middle;
+    let city = lots
+        .into_iter()
+        .map(|(x, y)| {
+            (
+                iso_project(x as f32, y as f32, iso_angle) - middle,
+                rng.gen_range(0.6..1.8),
+            )
+        })
+        .collect();
+
+    let render = args
+        .render_sequence
+        .as_ref()
+        .and_then(|dir| match std::fs::create_dir_all(dir) {
+            Ok(()) => Some(RenderSequence {
+                dir: dir.clone(),
+                fps: args.render_fps.max(1),
+                frame: 0,
+            }),
+            Err(e) => {
+                eprintln!(
+                    "Warning: couldn't create {} ({}), showing the animation live instead",
+                    dir.display(),
+                    e
+                );
+                None
+            }
+        });
+
+    let mut theme = args.material.theme();
+    for (face, alpha) in [
+        (&mut theme.left, args.left_alpha),
+        (&mut theme.right, args.right_alpha),
+        (&mut theme.top, args.top_alpha),
+    ] {
+        if let Some(alpha) = alpha {
+            face.alpha = alpha.clamp(0.0, 1.0);
+        }
+    }
 
+    Model {
+        building_height: 0.0,
+        building_animation_progress: 0.0,
+        window_animation_start_times,
+        timing,
+        elevators,
+        windows,
+        theme,
+        cast_shadows: args.cast_shadows,
+        light_angle: args.light_angle,
+        skyline,
+        city,
+        main_center,
+        iso_angle,
+        haze: Haze {
+            color: args.haze_color.unwrap_or_else(|| LINEN.into_format()),
+            strength: args.haze_strength.clamp(0.0, 1.0),
+        },
+        parallax: args.parallax,
+        rooftop: args.rooftop.map(|kind| Rooftop {
+            kind,
+            sway_rate: args.sway_rate,
+            blink_rate: args.blink_rate,
+        }),
+        time: 0.0,
+        start_time: 0.0,
+        render,
+        watermark: !args.no_watermark,
+        window,
+    }
+}
+
+/// Center of the lot at the given grid position. The grid's axes run towards the viewer
+/// along the two front edges of a building's footprint.
+fn iso_project(grid_x: f32, grid_y: f32, iso_angle: f32) -> Point2 {
+    vec2(grid_x - grid_y, -(grid_x + grid_y) * edge_slope(iso_angle)) * BASE_SIZE * CITY_SPACING
+}
+
+/// Rise over run of a footprint's edges, 1 at the default 45°.
+fn edge_slope(iso_angle: f32) -> f32 {
+    // tan(45° - angle), written so that an angle of 0 gives exactly 1.
+    let tan = iso_angle.tan();
+    (1.0 - tan) / (1.0 + tan)
+}
+
+/// Start time of each window, lighting them one after another in a random order.
+fn window_start_times(timing: &AnimationTiming, rng: &mut impl Rng) -> Vec<Vec<f32>> {
     // Create flat vector of all window indices
     let mut all_windows: Vec<(usize, usize)> = Vec::new();
     for i in 0..NUM_WINDOW_ROWS {
@@ -229,53 +1001,176 @@ fn model(app: &App) -> Model {
             all_windows.push((i as usize, j as usize));
         }
     }
-
-    let mut rng = rand::rngs::StdRng::from_entropy();
-    all_windows.shuffle(&mut rng);
+    all_windows.shuffle(rng);
 
     // Create animation start times matrix
-    let mut window_animation_start_times =
-        vec![vec![0.0; NUM_WINDOW_COLS as usize]; NUM_WINDOW_ROWS as usize];
+    let mut start_times = vec![vec![0.0; NUM_WINDOW_COLS as usize]; NUM_WINDOW_ROWS as usize];
     for (idx, (row, col)) in all_windows.iter().enumerate() {
-        window_animation_start_times[*row][*col] = 1.0 + (idx as f32 * WINDOW_ANIMATION_DELAY);
+        start_times[*row][*col] = timing.window_start + (idx as f32 * timing.window_delay);
     }
+    start_times
+}
 
-    Model {
-        building_height: 0.0,
-        building_animation_progress: 0.0,
-        window_animation_start_times,
+/// Restarts the build-up from an empty lot with the windows in a new order.
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if key != Key::R || model.render.is_some() {
+        return;
     }
+    model.start_time = app.time;
+    model.time = 0.0;
+    model.building_height = 0.0;
+    model.building_animation_progress = 0.0;
+    model.window_animation_start_times = window_start_times(&model.timing, &mut rand::thread_rng());
 }
 
 fn update(app: &App, model: &mut Model, _update: Update) {
-    model.building_animation_progress = (app.time * BUILDING_ANIMATION_SPEED).min(1.0);
+    // Rendering steps the clock a whole frame at a time so the output doesn't depend on
+    // how long each frame took to draw.
+    model.time = match &model.render {
+        Some(render) => render.frame as f32 / render.fps as f32,
+        None => app.time - model.start_time,
+    };
+    model.building_animation_progress = (model.time * model.timing.building_speed).min(1.0);
 
     // Calculate building height based on animation progress
     // Parameters: current time, start value, change in value, duration
     model.building_height =
         ease::cubic::ease_out(model.building_animation_progress, 0.0, BUILDING_HEIGHT, 1.0);
+
+    for elevator in &mut model.elevators {
+        elevator.update(model.time);
+    }
+
+    model.windows.update(
+        model.time,
+        &model.window_animation_start_times,
+        &model.timing,
+    );
+
+    if let Some(render) = &mut model.render {
+        if model.time > model.timing.total_duration() {
+            app.quit();
+            return;
+        }
+        let path = render.dir.join(format!("{:05}.png", render.frame));
+        if let Some(window) = app.window(model.window) {
+            window.capture_frame(path);
+        }
+        render.frame += 1;
+    }
+}
+
+/// Lets any frames still being written by `--render-sequence` finish before exiting.
+fn exit(app: &App, model: Model) {
+    if let Some(window) = app.window(model.window) {
+        if let Err(e) = window.await_capture_frame_jobs() {
+            eprintln!("Failed to write rendered frames: {}", e);
+        }
+    }
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
     draw.background().color(LINEN);
 
-    Building::new(pt2(0.0, 0.0), model.building_height).draw(&draw);
+    let scroll = if model.parallax {
+        model.time * SKYLINE_SCROLL_SPEED
+    } else {
+        0.0
+    };
+    let shadow = model.cast_shadows.then_some(model.light_angle);
+    for skyline_building in &model.skyline {
+        skyline_building.draw(
+            &draw,
+            &model.theme,
+            model.haze,
+            model.building_height,
+            scroll,
+            shadow,
+        );
+    }
+
+    let city: Vec<Building> = model
+        .city
+        .iter()
+        .map(|&(center, height)| {
+            Building::new(center, model.building_height * height, model.iso_angle)
+        })
+        .collect();
+    let building = Building::new(model.main_center, model.building_height, model.iso_angle);
+    // Shadows lie on the ground, so all of them go down before any building.
+    if model.cast_shadows {
+        for lot in city.iter().chain([&building]) {
+            lot.draw_shadow(&draw, model.light_angle);
+        }
+    }
+    for lot in city {
+        lot.draw(&draw, &model.theme);
+    }
+    let roof = building.roof();
+    building.draw(&draw, &model.theme);
     if model.building_animation_progress >= 1.0 {
-        Windows::new().draw(&draw, app.time, &model.window_animation_start_times);
+        if let Some(rooftop) = &model.rooftop {
+            rooftop.draw(&draw, roof, model.time);
+        }
+        model.windows.draw(
+            &draw.translate(model.main_center.extend(0.0)),
+            &model.elevators,
+            &model.theme,
+        );
+    }
+    if model.watermark {
+        draw_watermark(
+            &draw,
+            app.window_rect(),
+            "1.20",
+            Corner::BottomLeft,
+            None,
+            LINEN.into_format(),
+        );
     }
-    watermark(&draw);
 
     draw.to_frame(app, &frame).unwrap();
 }
 
-fn watermark(draw: &Draw) {
-    draw.text("1.20")
-        .color(rgba(0.0, 0.0, 0.0, 0.5))
-        .font_size(24)
-        .align_text_bottom()
-        .x_y(
-            -(OS_WINDOW_WIDTH as f32) / 2.0 + 40.0,
-            -(OS_WINDOW_HEIGHT as f32) / 2.0 + 110.0,
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corner_windows_mirror_across_the_front_edge() {
+        let last_col = NUM_WINDOW_COLS as usize - 1;
+        for iso_angle in [-MAX_ISO_ANGLE, 0.0, MAX_ISO_ANGLE] {
+            for row in 0..NUM_WINDOW_ROWS as usize {
+                for from_corner in 0..=last_col {
+                    let left = Window::new(
+                        row,
+                        last_col - from_corner,
+                        Side::Left,
+                        true,
+                        None,
+                        iso_angle,
+                    );
+                    let right = Window::new(row, from_corner, Side::Right, true, None, iso_angle);
+
+                    assert!((left.center.x + right.center.x).abs() < 1e-4);
+                    assert!((left.center.y - right.center.y).abs() < 1e-4);
+                    // Mirrored, the left window's corners run in the opposite order.
+                    for (l, r) in left.vertices.iter().zip(right.vertices.iter().rev()) {
+                        assert!((l.x + r.x).abs() < 1e-4 && (l.y - r.y).abs() < 1e-4);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn innermost_windows_sit_either_side_of_the_corner() {
+        let last_col = NUM_WINDOW_COLS as usize - 1;
+        let left = Window::new(0, last_col, Side::Left, true, None, 0.0);
+        let right = Window::new(0, 0, Side::Right, true, None, 0.0);
+        let gap = BASE_SIZE / 4.0;
+        assert!((left.center.x + gap / 2.0).abs() < 1e-4);
+        assert!((right.center.x - gap / 2.0).abs() < 1e-4);
+    }
 }
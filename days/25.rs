@@ -1,20 +1,325 @@
 extern crate time;
 extern crate travelling_salesman;
+use clap::{Parser, ValueEnum};
+use nannou::image::{Rgba, RgbaImage};
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::attach_capture;
+use nannou_genuary_2025::common::lerp::lerp_point;
+use nannou_genuary_2025::common::watermark::{corner_point, draw_watermark, Corner};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Instant;
 
 const OS_WINDOW_WIDTH: u32 = 800;
 const OS_WINDOW_HEIGHT: u32 = 800;
-const NUM_COORDS: usize = 50;
+const NUM_COORDS: usize = 50; // Random points per tour when no `--coords` file is given
 const SOLUTION_VIEW_TIME: f32 = 0.5;
 const COORDS_ANIMATION_SPEED: f32 = 0.05;
 const EDGES_ANIMATION_SPEED: f32 = 0.4;
 const MAX_TSP_SOLUTION_TIME_MILLISECONDS: i64 = 200;
+const MIN_SOLVE_BUDGET_MILLISECONDS: i64 = 25; // Range the solve budget can be set to live
+const MAX_SOLVE_BUDGET_MILLISECONDS: i64 = 6400;
+const TWO_OPT_MOVE_FRAMES: u32 = 6; // Frames each 2-opt move is shown before it's applied
+const LIVE_TWO_OPT_SWAPS: usize = 2; // Improving 2-opt moves made per frame under `--optimize-live`
+const ORBIT_RESOLVE_SECONDS: f32 = 1.0; // Time between re-solves in `--orbit` mode
+const ORBIT_MIN_RADIUS: f32 = 10.0;
+const ORBIT_MAX_RADIUS: f32 = 50.0;
+const ORBIT_MAX_SPEED: f32 = 0.8; // Radians per second
+const MAX_PRIORITY_WEIGHT: u32 = 3; // Priority points get a weight between 1 and this
+const GRID_DIVISIONS: u32 = 8; // Grid cells across the window when no spacing is given
+const ANNEAL_ITERATIONS: usize = 200_000;
+const ANNEAL_START_TEMPERATURE: f64 = 100.0;
+const ANNEAL_END_TEMPERATURE: f64 = 0.01;
+const ANNEAL_SAMPLES: usize = 200; // Points recorded along the cooling curve
+const TEMPERATURE_GRAPH_WIDTH: f32 = 200.0;
+const TEMPERATURE_GRAPH_HEIGHT: f32 = 100.0;
+const SPLINE_SEGMENTS: usize = 16; // Straight pieces each curved edge is drawn with
+const VORONOI_CELL_SIZE: u32 = 10; // Pixels per side of each cell the Voronoi backdrop is sampled at
+const FRAMES_PER_SECOND: f32 = 60.0; // Frame rate `SOLUTION_VIEW_TIME` is converted to frames at
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Travelling salesman visualization using nannou"
+)]
+struct Args {
+    /// Draw the pairwise distance matrix as a heatmap inset
+    #[arg(long)]
+    matrix: bool,
+
+    /// Side length of the distance matrix inset, in pixels
+    #[arg(long, default_value_t = 160.0)]
+    matrix_size: f32,
+
+    /// Save a print-resolution render of the first completed tour to this PNG
+    #[arg(long)]
+    poster: Option<PathBuf>,
+
+    /// Resolution multiplier for `--poster`
+    #[arg(long, default_value_t = 4)]
+    poster_scale: u32,
+
+    /// Heuristic used to find each tour
+    #[arg(long, value_enum, default_value_t = Solver::Annealing)]
+    solver: Solver,
+
+    /// Instead of opening a window, compare every solver on this many random point sets
+    /// and print the results as CSV
+    #[arg(long)]
+    bench: Option<usize>,
+
+    /// Animate each improving 2-opt move, showing the crossing edges it removes and the
+    /// edges replacing them (implies `--solver two-opt`)
+    #[arg(long = "show-2opt")]
+    show_two_opt: bool,
+
+    /// Untangle the tour on screen with 2-opt, a few moves a frame, instead of showing the
+    /// solver's finished tour
+    #[arg(
+        long,
+        conflicts_with_all = ["orbit", "show_two_opt", "loop_frames", "solver", "show_temp"]
+    )]
+    optimize_live: bool,
+
+    /// Keep every point circling its own small orbit and re-solve the tour periodically
+    /// in the background instead of jumping to new positions
+    #[arg(long)]
+    orbit: bool,
+
+    /// Count the places where the finished tour crosses itself and mark them
+    #[arg(long)]
+    count_crossings: bool,
+
+    /// Give this many points a priority weight and bias each tour to visit them early
+    #[arg(long)]
+    priority: Option<usize>,
+
+    /// Extra length a tour may take to visit a weight-1 point one stop earlier
+    #[arg(long, default_value_t = 10.0)]
+    priority_penalty: f64,
+
+    /// Draw a faint labelled coordinate grid behind the points
+    #[arg(long)]
+    grid_overlay: bool,
+
+    /// Distance between grid lines in pixels (defaults to an eighth of the window)
+    #[arg(long)]
+    grid_spacing: Option<f32>,
+
+    /// Label each edge of the finished tour with its length
+    #[arg(long)]
+    edge_labels: bool,
+
+    /// Font size of the `--edge-labels`
+    #[arg(long, default_value_t = 10)]
+    edge_label_size: u32,
+
+    /// Solve with a traced simulated annealing and graph its temperature and tour length
+    /// as it cools
+    #[arg(long)]
+    show_temp: bool,
+
+    /// Draw the tour as a smooth Catmull-Rom curve through the points
+    #[arg(long)]
+    spline: bool,
+
+    /// Tint the background by which point is nearest, once the points settle
+    #[arg(long)]
+    voronoi: bool,
+
+    /// Fit the whole move, solve, draw and view cycle into exactly this many frames so
+    /// recordings loop cleanly
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u32).range(3..),
+        conflicts_with_all = ["orbit", "show_two_opt"]
+    )]
+    loop_frames: Option<u32>,
+
+    /// Grow the minimum spanning tree of the points edge by edge, over the tour or in
+    /// place of it
+    #[arg(long, value_enum)]
+    mst: Option<MstMode>,
+
+    /// Tour the points in this file instead of random ones: one `x,y` pair per line, or a
+    /// TSPLIB file with a `NODE_COORD_SECTION`. They're scaled to fit the window.
+    #[arg(long)]
+    coords: Option<PathBuf>,
+
+    /// Color each tour edge from green to red by its length against the tour's longest
+    /// edge, to pick out the long jumps
+    #[arg(long)]
+    length_colors: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum MstMode {
+    Overlay, // Draw the tree on top of the tour
+    Only,    // Draw the tree instead of the tour
+}
+
+/// A small circle a point drifts around in `--orbit` mode.
+struct Orbit {
+    center: Point2,
+    radius: f32,
+    speed: f32, // Radians per second, negative for clockwise
+    phase: f32,
+}
+
+impl Orbit {
+    fn around(center: Point2) -> Self {
+        Orbit {
+            center,
+            radius: random_range(ORBIT_MIN_RADIUS, ORBIT_MAX_RADIUS),
+            speed: random_range(-ORBIT_MAX_SPEED, ORBIT_MAX_SPEED),
+            phase: random_range(0.0, TAU),
+        }
+    }
+
+    fn position(&self, time: f32) -> Point2 {
+        let angle = self.phase + self.speed * time;
+        self.center + vec2(angle.cos(), angle.sin()) * self.radius
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Solver {
+    Annealing,
+    HillClimbing,
+    TwoOpt,
+    #[value(name = "nn")]
+    NearestNeighbor,
+    BranchBound,
+}
+
+impl Solver {
+    const ALL: [Solver; 5] = [
+        Solver::Annealing,
+        Solver::HillClimbing,
+        Solver::TwoOpt,
+        Solver::NearestNeighbor,
+        Solver::BranchBound,
+    ];
+
+    /// Returns the route found and its length. The heuristics give up improving it after
+    /// `budget_ms` milliseconds.
+    fn solve(self, points: &[(f64, f64)], budget_ms: i64) -> (Vec<usize>, f64) {
+        let budget = time::Duration::milliseconds(budget_ms);
+        match self {
+            Solver::Annealing => {
+                let tour = travelling_salesman::simulated_annealing::solve(points, budget);
+                (tour.route, tour.distance)
+            }
+            Solver::HillClimbing => {
+                let tour = travelling_salesman::hill_climbing::solve(points, budget);
+                (tour.route, tour.distance)
+            }
+            Solver::TwoOpt => {
+                let (route, _) = two_opt(points);
+                let distance = route_length(points, &route);
+                (route, distance)
+            }
+            Solver::NearestNeighbor => {
+                let route = nearest_neighbor(points);
+                let distance = route_length(points, &route);
+                (route, distance)
+            }
+            Solver::BranchBound => {
+                let deadline = Instant::now() + std::time::Duration::from_millis(budget_ms as u64);
+                let route = branch_and_bound(points, deadline);
+                let distance = route_length(points, &route);
+                (route, distance)
+            }
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Solver::Annealing => "annealing",
+            Solver::HillClimbing => "hill-climbing",
+            Solver::TwoOpt => "two-opt",
+            Solver::NearestNeighbor => "nn",
+            Solver::BranchBound => "branch-bound",
+        }
+    }
+}
 
 #[derive(Clone)]
 enum ModelState {
     DrawingEdges,    // Draw the solution connecting all points
     ViewingSolution, // Pause to view the complete solution
     MovingCoords,    // Move the coordinates to a new random location
+    ShowingTwoOpt,   // Step through the 2-opt moves that improve the starting tour
+    Optimizing,      // Improve the tour with 2-opt moves a few at a time, drawn as it goes
+}
+
+/// An improving 2-opt move: reversing `route[i + 1..=j]` swaps the edges leaving positions
+/// `i` and `j` for two shorter ones that no longer cross.
+#[derive(Clone, Copy)]
+struct TwoOptMove {
+    i: usize,
+    j: usize,
+}
+
+impl TwoOptMove {
+    /// The two edges this move removes from `route`, then the two it adds.
+    fn edges(self, route: &[usize]) -> ([(usize, usize); 2], [(usize, usize); 2]) {
+        let n = route.len();
+        let (a, b) = (route[self.i], route[self.i + 1]);
+        let (c, d) = (route[self.j], route[(self.j + 1) % n]);
+        ([(a, b), (c, d)], [(a, c), (b, d)])
+    }
+
+    fn apply(self, route: &mut [usize]) {
+        route[self.i + 1..=self.j].reverse();
+    }
+}
+
+/// How far each state's progress advances per frame.
+#[derive(Clone, Copy)]
+struct Pacing {
+    coords: f32,
+    edges: f32,
+    view: Option<f32>, // Seconds of `SOLUTION_VIEW_TIME` per frame, or real time when `None`
+}
+
+impl Pacing {
+    fn natural() -> Self {
+        Pacing {
+            coords: COORDS_ANIMATION_SPEED,
+            edges: EDGES_ANIMATION_SPEED,
+            view: None,
+        }
+    }
+
+    /// Shares `loop_frames` between the states in proportion to their natural lengths.
+    fn looping(loop_frames: u32, num_coords: usize) -> Self {
+        let natural = [
+            1.0 / COORDS_ANIMATION_SPEED,
+            num_coords as f32 / EDGES_ANIMATION_SPEED,
+            SOLUTION_VIEW_TIME * FRAMES_PER_SECOND,
+        ];
+        let total: f32 = natural.iter().sum();
+        let boundary = |length: f32| (length / total * loop_frames as f32).round() as u32;
+        let moving = boundary(natural[0]).clamp(1, loop_frames - 2);
+        let drawing = boundary(natural[0] + natural[1]).clamp(moving + 1, loop_frames - 1) - moving;
+        let viewing = loop_frames - moving - drawing;
+        println!(
+            "Looping every {} frames: {} moving, {} drawing, {} viewing",
+            loop_frames, moving, drawing, viewing
+        );
+
+        // Each state ends when its progress passes a threshold. Aiming half a frame short
+        // of it makes that happen on the last frame however the additions round.
+        let step = |threshold: f32, frames: u32| threshold / (frames as f32 - 0.5);
+        Pacing {
+            coords: step(1.0, moving),
+            edges: step(num_coords as f32, drawing),
+            view: Some(step(SOLUTION_VIEW_TIME, viewing)),
+        }
+    }
 }
 
 struct ModelAnimationProgress {
@@ -25,38 +330,127 @@ struct ModelAnimationProgress {
 
 struct Model {
     coords: Vec<Point2>,        // Current coordinates
-    target_coords: Vec<Point2>, // Random target coordinates to move to
+    target_coords: Vec<Point2>, // Target coordinates to move to
+    num_coords: usize,          // Points in each tour
+    fixed_coords: bool,         // Points came from `--coords`, so every tour uses the same ones
     animations: ModelAnimationProgress,
     state: ModelState,
-    current_tour: Vec<usize>, // Current TSP solution
-    tour_length: f64,         // Length of current tour
+    current_tour: Vec<usize>,       // Current TSP solution
+    tour_length: f64,               // Length of current tour
+    distance_matrix: Vec<Vec<f32>>, // Pairwise distances for the current point set
+    poster_saved: bool,
+    two_opt_moves: Vec<TwoOptMove>, // Moves still to show under `--show-2opt`
+    two_opt_frame: u32,             // Frames the current move has been shown for
+    two_opt_cursor: TwoOptMove,     // Next move `--optimize-live` will consider
+    two_opt_stale: usize,           // Moves considered since the last improving one
+    orbits: Vec<Orbit>,             // Paths of the points under `--orbit`
+    pending_tour: Option<Receiver<(Vec<usize>, f64)>>, // Background solve in flight
+    last_solve_time: f32,
+    crossings: Vec<Point2>, // Where the finished tour crosses itself, under `--count-crossings`
+    priorities: Vec<f64>,   // Weight of each point under `--priority`, 0 for ordinary points
+    cooling: Vec<CoolingSample>, // Trace of the last solve under `--show-temp`
+    voronoi: Vec<usize>,    // Nearest point to each backdrop cell, row by row from the bottom
+    pacing: Pacing,
+    solve_budget_ms: i64, // Time the next solve may take, changed with + and -
+    mst: Vec<(usize, usize)>, // Minimum spanning tree under `--mst`, in the order it grows
+    args: Args,
 }
 
 fn main() {
+    if let Some(instances) = Args::parse().bench {
+        bench(instances);
+        return;
+    }
     nannou::app(model).update(update).run();
 }
 
-fn model(app: &App) -> Model {
-    app.new_window()
-        .size(OS_WINDOW_WIDTH, OS_WINDOW_HEIGHT)
-        .view(view)
-        .build()
-        .unwrap();
+/// Solves `instances` random point sets with every solver and prints length and timing
+/// statistics per solver as CSV.
+fn bench(instances: usize) {
+    let point_sets: Vec<Vec<(f64, f64)>> = (0..instances)
+        .map(|_| {
+            let coords: Vec<Point2> = (0..NUM_COORDS).map(|_| random_point()).collect();
+            solver_points(&coords)
+        })
+        .collect();
 
-    // Initialize all points at the center
-    let mut coords = Vec::new();
-    let mut target_coords = Vec::new();
-    let mut coord_animation_progress = Vec::new();
+    println!("solver,instances,mean_length,median_length,mean_ms,median_ms");
+    for solver in Solver::ALL {
+        let mut lengths = Vec::with_capacity(instances);
+        let mut millis = Vec::with_capacity(instances);
+        for points in &point_sets {
+            let start = Instant::now();
+            let (_, distance) = solver.solve(points, MAX_TSP_SOLUTION_TIME_MILLISECONDS);
+            millis.push(start.elapsed().as_secs_f64() * 1000.0);
+            lengths.push(distance);
+        }
+        println!(
+            "{},{},{:.1},{:.1},{:.1},{:.1}",
+            solver.name(),
+            instances,
+            mean(&lengths),
+            median(&mut lengths),
+            mean(&millis),
+            median(&mut millis),
+        );
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
 
-    for _ in 0..NUM_COORDS {
-        coords.push(pt2(0.0, 0.0));
-        target_coords.push(random_point());
-        coord_animation_progress.push(0.0);
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
     }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+fn model(app: &App) -> Model {
+    let args = Args::parse();
+    attach_capture(
+        app.new_window()
+            .size(OS_WINDOW_WIDTH, OS_WINDOW_HEIGHT)
+            .view(view)
+            .key_pressed(key_pressed),
+    )
+    .build()
+    .unwrap();
+
+    let loaded = args.coords.as_deref().map(|path| {
+        load_coords(path).unwrap_or_else(|e| {
+            eprintln!(
+                "Error: couldn't load coordinates {} ({})",
+                path.display(),
+                e
+            );
+            std::process::exit(1);
+        })
+    });
+    let fixed_coords = loaded.is_some();
+    let target_coords = loaded.unwrap_or_else(|| (0..NUM_COORDS).map(|_| random_point()).collect());
+    let num_coords = target_coords.len();
+
+    // Initialize all points at the center
+    let coords = vec![pt2(0.0, 0.0); num_coords];
+    let coord_animation_progress = vec![0.0; num_coords];
 
     Model {
         coords,
+        orbits: target_coords.iter().map(|&c| Orbit::around(c)).collect(),
         target_coords,
+        num_coords,
+        fixed_coords,
         animations: ModelAnimationProgress {
             coord_animation_progress,
             edge_animation_progress: 0.0,
@@ -65,22 +459,58 @@ fn model(app: &App) -> Model {
         state: ModelState::MovingCoords,
         current_tour: Vec::new(),
         tour_length: 0.0,
+        distance_matrix: Vec::new(),
+        poster_saved: false,
+        two_opt_moves: Vec::new(),
+        two_opt_frame: 0,
+        two_opt_cursor: TwoOptMove { i: 0, j: 2 },
+        two_opt_stale: 0,
+        pending_tour: None,
+        last_solve_time: f32::NEG_INFINITY,
+        crossings: Vec::new(),
+        priorities: random_priorities(args.priority.unwrap_or(0), num_coords),
+        cooling: Vec::new(),
+        voronoi: Vec::new(),
+        pacing: args.loop_frames.map_or_else(Pacing::natural, |frames| {
+            Pacing::looping(frames, num_coords)
+        }),
+        solve_budget_ms: MAX_TSP_SOLUTION_TIME_MILLISECONDS,
+        mst: Vec::new(),
+        args,
     }
 }
 
-fn update(_app: &App, model: &mut Model, update: Update) {
+/// + and - double and halve the time the next solve gets.
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    let budget = match key {
+        Key::Plus | Key::Equals | Key::NumpadAdd => model.solve_budget_ms * 2,
+        Key::Minus | Key::NumpadSubtract => model.solve_budget_ms / 2,
+        _ => return,
+    };
+    model.solve_budget_ms =
+        budget.clamp(MIN_SOLVE_BUDGET_MILLISECONDS, MAX_SOLVE_BUDGET_MILLISECONDS);
+}
+
+fn update(app: &App, model: &mut Model, update: Update) {
+    if model.args.orbit {
+        update_orbiting(app, model);
+        return;
+    }
+
     match model.state {
         ModelState::MovingCoords => update_moving_coords(model),
         ModelState::DrawingEdges => update_drawing_edges(model),
         ModelState::ViewingSolution => update_viewing_solution(model, update),
+        ModelState::ShowingTwoOpt => update_showing_two_opt(model),
+        ModelState::Optimizing => update_optimizing(model),
     }
 }
 
 fn update_moving_coords(model: &mut Model) {
     let mut all_arrived = true;
 
-    for i in 0..NUM_COORDS {
-        model.animations.coord_animation_progress[i] += COORDS_ANIMATION_SPEED;
+    for i in 0..model.num_coords {
+        model.animations.coord_animation_progress[i] += model.pacing.coords;
         if model.animations.coord_animation_progress[i] > 1.0 {
             model.animations.coord_animation_progress[i] = 1.0;
             model.coords[i] = model.target_coords[i];
@@ -88,54 +518,499 @@ fn update_moving_coords(model: &mut Model) {
             all_arrived = false;
             // Interpolate between current and target position
             let t = model.animations.coord_animation_progress[i];
-            model.coords[i] = pt2(
-                lerp(model.coords[i].x, model.target_coords[i].x, t),
-                lerp(model.coords[i].y, model.target_coords[i].y, t),
-            );
+            model.coords[i] = lerp_point(model.coords[i], model.target_coords[i], t);
         }
     }
 
     if all_arrived {
-        // Convert coordinates to the format expected by the TSP solver
-        let points: Vec<(f64, f64)> = model
-            .coords
+        let points = solver_points(&model.coords);
+        if model.args.matrix {
+            model.distance_matrix = distance_matrix(&model.coords);
+        }
+        if model.args.voronoi {
+            model.voronoi = voronoi_cells(&model.coords);
+        }
+        if model.args.mst.is_some() {
+            model.mst = minimum_spanning_tree(&model.coords);
+        }
+
+        if model.args.show_two_opt {
+            // Start from the unoptimized tour and replay the moves on top of it.
+            let (_, mut moves) = two_opt(&points);
+            moves.reverse(); // Popped from the back
+            model.current_tour = (0..model.num_coords).collect();
+            model.tour_length = route_length(&points, &model.current_tour);
+            model.two_opt_moves = moves;
+            model.two_opt_frame = 0;
+            model.state = ModelState::ShowingTwoOpt;
+            return;
+        }
+
+        if model.args.optimize_live {
+            model.current_tour = (0..model.num_coords).collect();
+            model.tour_length = route_length(&points, &model.current_tour);
+            model.two_opt_cursor = TwoOptMove { i: 0, j: 2 };
+            model.two_opt_stale = 0;
+            model.animations.edge_animation_progress = model.num_coords as f32;
+            model.state = ModelState::Optimizing;
+            return;
+        }
+
+        let (route, distance) = if model.args.show_temp {
+            let (route, cooling) = traced_annealing(&points);
+            model.cooling = cooling;
+            let distance = route_length(&points, &route);
+            (route, distance)
+        } else {
+            model.args.solver.solve(&points, model.solve_budget_ms)
+        };
+        model.current_tour = route;
+        model.tour_length = distance;
+        prioritize(model);
+        model.state = ModelState::DrawingEdges;
+        model.animations.edge_animation_progress = 0.0;
+    }
+}
+
+/// Moves the points along their orbits, picks up any finished solve, and starts the next
+/// one on a worker thread once the cadence comes round. The last tour is kept in the
+/// meantime; it still visits the same points, just no longer optimally.
+fn update_orbiting(app: &App, model: &mut Model) {
+    for (coord, orbit) in model.coords.iter_mut().zip(&model.orbits) {
+        *coord = orbit.position(app.time);
+    }
+
+    if let Some(receiver) = &model.pending_tour {
+        match receiver.try_recv() {
+            Ok((route, _)) => {
+                model.pending_tour = None;
+                model.current_tour = route;
+                prioritize(model);
+                finish_tour(model);
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+            Err(mpsc::TryRecvError::Disconnected) => model.pending_tour = None,
+        }
+    }
+
+    let points = solver_points(&model.coords);
+    if model.pending_tour.is_none() && app.time - model.last_solve_time >= ORBIT_RESOLVE_SECONDS {
+        let solver = model.args.solver;
+        let budget_ms = model.solve_budget_ms;
+        let solve_points = points.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // The receiver is dropped if the app exits mid-solve; nothing to do then.
+            let _ = sender.send(solver.solve(&solve_points, budget_ms));
+        });
+        model.pending_tour = Some(receiver);
+        model.last_solve_time = app.time;
+    }
+
+    if !model.current_tour.is_empty() {
+        model.tour_length = route_length(&points, &model.current_tour);
+    }
+    if model.args.mst.is_some() {
+        model.mst = minimum_spanning_tree(&model.coords);
+    }
+    // The points keep moving, so the crossings do too.
+    if model.args.count_crossings {
+        model.crossings = tour_crossings(&model.coords, &model.current_tour);
+    }
+    if model.args.matrix {
+        model.distance_matrix = distance_matrix(&model.coords);
+    }
+    if model.args.voronoi {
+        model.voronoi = voronoi_cells(&model.coords);
+    }
+}
+
+/// Converts coordinates to the format expected by the TSP solver.
+fn solver_points(coords: &[Point2]) -> Vec<(f64, f64)> {
+    coords
+        .iter()
+        .map(|p| {
+            (
+                (p.x + OS_WINDOW_WIDTH as f32 / 2.0) as f64,
+                (p.y + OS_WINDOW_HEIGHT as f32 / 2.0) as f64,
+            )
+        })
+        .collect()
+}
+
+/// Length of the closed tour visiting `points` in `route` order.
+fn route_length(points: &[(f64, f64)], route: &[usize]) -> f64 {
+    (0..route.len())
+        .map(|i| point_distance(points[route[i]], points[route[(i + 1) % route.len()]]))
+        .sum()
+}
+
+fn point_distance((ax, ay): (f64, f64), (bx, by): (f64, f64)) -> f64 {
+    (ax - bx).hypot(ay - by)
+}
+
+/// Improves the tour visiting `points` in index order with 2-opt until no move shortens
+/// it, returning the final route and every move taken to reach it.
+fn two_opt(points: &[(f64, f64)]) -> (Vec<usize>, Vec<TwoOptMove>) {
+    let n = points.len();
+    let mut route: Vec<usize> = (0..n).collect();
+    let mut moves = Vec::new();
+    let distance = |a: usize, b: usize| point_distance(points[a], points[b]);
+
+    let mut improved = n > 3;
+    while improved {
+        improved = false;
+        for i in 0..n - 2 {
+            // Skip j = n - 1 when i = 0: those two edges share a point.
+            let last = if i == 0 { n - 2 } else { n - 1 };
+            for j in i + 2..=last {
+                let candidate = TwoOptMove { i, j };
+                let ([(a, b), (c, d)], [(e, f), (g, h)]) = candidate.edges(&route);
+                let delta = distance(e, f) + distance(g, h) - distance(a, b) - distance(c, d);
+                if delta < -1e-9 {
+                    candidate.apply(&mut route);
+                    moves.push(candidate);
+                    improved = true;
+                }
+            }
+        }
+    }
+    (route, moves)
+}
+
+/// Starts at the first point and keeps going to the closest one not yet visited.
+fn nearest_neighbor(points: &[(f64, f64)]) -> Vec<usize> {
+    let mut unvisited: Vec<usize> = (1..points.len()).collect();
+    let mut route = Vec::with_capacity(points.len());
+    if points.is_empty() {
+        return route;
+    }
+    route.push(0);
+    while !unvisited.is_empty() {
+        let here = points[route[route.len() - 1]];
+        let (nearest, _) = unvisited
             .iter()
-            .map(|p| {
-                (
-                    (p.x + OS_WINDOW_WIDTH as f32 / 2.0) as f64,
-                    (p.y + OS_WINDOW_HEIGHT as f32 / 2.0) as f64,
-                )
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                point_distance(here, points[**a]).total_cmp(&point_distance(here, points[**b]))
             })
+            .unwrap();
+        route.push(unvisited.swap_remove(nearest));
+    }
+    route
+}
+
+/// Depth-first search over every tour from the first point, dropping any partial tour
+/// that can't beat the best so far even by heading straight home. It starts from the
+/// nearest neighbor tour and hands back the best found once `deadline` passes, which for
+/// more than a dozen or so points comes long before the search is exhaustive.
+fn branch_and_bound(points: &[(f64, f64)], deadline: Instant) -> Vec<usize> {
+    let best = nearest_neighbor(points);
+    if points.len() < 4 {
+        return best;
+    }
+    let mut search = BranchAndBound {
+        points,
+        deadline,
+        best_length: route_length(points, &best),
+        best,
+        path: vec![0],
+        visited: vec![false; points.len()],
+        nodes: 0,
+    };
+    search.visited[0] = true;
+    search.extend(0.0);
+    search.best
+}
+
+struct BranchAndBound<'a> {
+    points: &'a [(f64, f64)],
+    deadline: Instant,
+    best: Vec<usize>,
+    best_length: f64,
+    path: Vec<usize>, // The partial tour being extended
+    visited: Vec<bool>,
+    nodes: u64, // Partial tours tried, to check the clock only every so often
+}
+
+impl BranchAndBound<'_> {
+    /// Tries every way of finishing `path`, which is `length` long so far. Returns false
+    /// once the deadline has passed.
+    fn extend(&mut self, length: f64) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(1024) && Instant::now() >= self.deadline {
+            return false;
+        }
+
+        let here = self.points[self.path[self.path.len() - 1]];
+        let home = self.points[0];
+        if self.path.len() == self.points.len() {
+            let total = length + point_distance(here, home);
+            if total < self.best_length {
+                self.best_length = total;
+                self.best = self.path.clone();
+            }
+            return true;
+        }
+
+        // Nearest first, so good tours turn up early and prune more of the rest.
+        let mut next: Vec<usize> = (0..self.points.len())
+            .filter(|&i| !self.visited[i])
             .collect();
+        next.sort_by(|&a, &b| {
+            point_distance(here, self.points[a]).total_cmp(&point_distance(here, self.points[b]))
+        });
+        for i in next {
+            let length = length + point_distance(here, self.points[i]);
+            // By the triangle inequality no tour through here can be shorter than going
+            // straight home from the next point.
+            if length + point_distance(self.points[i], home) >= self.best_length {
+                continue;
+            }
+            self.visited[i] = true;
+            self.path.push(i);
+            let in_time = self.extend(length);
+            self.path.pop();
+            self.visited[i] = false;
+            if !in_time {
+                return false;
+            }
+        }
+        true
+    }
+}
 
-        // Solve TSP
-        let tour = travelling_salesman::simulated_annealing::solve(
-            &points,
-            time::Duration::milliseconds(MAX_TSP_SOLUTION_TIME_MILLISECONDS),
-        );
+/// The temperature and tour length part way through `traced_annealing`.
+struct CoolingSample {
+    temperature: f64,
+    length: f64,
+}
 
-        model.current_tour = tour.route;
-        model.tour_length = tour.distance;
-        model.state = ModelState::DrawingEdges;
-        model.animations.edge_animation_progress = 0.0;
+/// Simulated annealing over 2-opt moves with a geometric cooling schedule. The crate's
+/// solver keeps its temperature to itself, so this one records it for `--show-temp`.
+fn traced_annealing(points: &[(f64, f64)]) -> (Vec<usize>, Vec<CoolingSample>) {
+    let n = points.len();
+    let mut route: Vec<usize> = (0..n).collect();
+    let mut length = route_length(points, &route);
+    let mut samples = Vec::with_capacity(ANNEAL_SAMPLES);
+    if n < 4 {
+        return (route, samples);
+    }
+    let distance = |a: usize, b: usize| point_distance(points[a], points[b]);
+    let sample_every = (ANNEAL_ITERATIONS / ANNEAL_SAMPLES).max(1);
+
+    for k in 0..ANNEAL_ITERATIONS {
+        let progress = k as f64 / ANNEAL_ITERATIONS as f64;
+        let temperature = ANNEAL_START_TEMPERATURE
+            * (ANNEAL_END_TEMPERATURE / ANNEAL_START_TEMPERATURE).powf(progress);
+        if k.is_multiple_of(sample_every) {
+            samples.push(CoolingSample {
+                temperature,
+                length,
+            });
+        }
+
+        let i = random_range(0, n - 2);
+        let j = random_range(i + 2, n);
+        // Those two edges share a point.
+        if i == 0 && j == n - 1 {
+            continue;
+        }
+        let candidate = TwoOptMove { i, j };
+        let ([(a, b), (c, d)], [(e, f), (g, h)]) = candidate.edges(&route);
+        let delta = distance(e, f) + distance(g, h) - distance(a, b) - distance(c, d);
+        if delta < 0.0 || random_f64() < (-delta / temperature).exp() {
+            candidate.apply(&mut route);
+            length += delta;
+        }
+    }
+    (route, samples)
+}
+
+fn update_showing_two_opt(model: &mut Model) {
+    model.two_opt_frame += 1;
+    if model.two_opt_frame < TWO_OPT_MOVE_FRAMES {
+        return;
+    }
+    model.two_opt_frame = 0;
+
+    match model.two_opt_moves.pop() {
+        Some(next) => {
+            next.apply(&mut model.current_tour);
+            model.tour_length = route_length(&solver_points(&model.coords), &model.current_tour);
+        }
+        None => {
+            prioritize(model);
+            finish_tour(model);
+        }
+    }
+}
+
+/// Works through the 2-opt moves in the same order as `two_opt`, stopping for the frame
+/// after a few improve the tour. Once a whole lap of moves goes by without one, the tour
+/// is at a local optimum and is finished.
+fn update_optimizing(model: &mut Model) {
+    let points = solver_points(&model.coords);
+    let n = points.len();
+    let distance = |a: usize, b: usize| point_distance(points[a], points[b]);
+    // Each i pairs with every j from i + 2 up to n - 1, except i = 0 stops at n - 2.
+    let total_moves = if n > 3 { n * (n - 3) / 2 } else { 0 };
+
+    let mut swaps = 0;
+    while swaps < LIVE_TWO_OPT_SWAPS && model.two_opt_stale < total_moves {
+        let candidate = model.two_opt_cursor;
+        let ([(a, b), (c, d)], [(e, f), (g, h)]) = candidate.edges(&model.current_tour);
+        let delta = distance(e, f) + distance(g, h) - distance(a, b) - distance(c, d);
+        if delta < -1e-9 {
+            candidate.apply(&mut model.current_tour);
+            model.tour_length += delta;
+            model.two_opt_stale = 0;
+            swaps += 1;
+        } else {
+            model.two_opt_stale += 1;
+        }
+
+        let TwoOptMove { mut i, mut j } = candidate;
+        let last = if i == 0 { n - 2 } else { n - 1 };
+        j += 1;
+        if j > last {
+            i = if i + 1 < n - 2 { i + 1 } else { 0 };
+            j = i + 2;
+        }
+        model.two_opt_cursor = TwoOptMove { i, j };
+    }
+
+    if model.two_opt_stale >= total_moves {
+        model.tour_length = route_length(&points, &model.current_tour);
+        prioritize(model);
+        finish_tour(model);
+    }
+}
+
+/// Weights for `count` randomly chosen points out of `num_coords`, the rest left at 0.
+fn random_priorities(count: usize, num_coords: usize) -> Vec<f64> {
+    let mut priorities = vec![0.0; num_coords];
+    let mut indices: Vec<usize> = (0..num_coords).collect();
+    for _ in 0..count.min(num_coords) {
+        let index = indices.swap_remove(random_range(0, indices.len()));
+        priorities[index] = random_range(1, MAX_PRIORITY_WEIGHT + 1) as f64;
+    }
+    priorities
+}
+
+/// Reorders the current tour so priority points come early, if `--priority` is on.
+fn prioritize(model: &mut Model) {
+    if model.args.priority.is_none() {
+        return;
+    }
+    let points = solver_points(&model.coords);
+    prioritize_route(
+        &points,
+        &mut model.current_tour,
+        &model.priorities,
+        model.args.priority_penalty,
+    );
+    model.tour_length = route_length(&points, &model.current_tour);
+}
+
+/// Route length plus `penalty` for every stop each priority point waits, times its weight.
+fn priority_cost(points: &[(f64, f64)], route: &[usize], weights: &[f64], penalty: f64) -> f64 {
+    let waiting: f64 = route
+        .iter()
+        .enumerate()
+        .map(|(position, &point)| weights[point] * position as f64)
+        .sum();
+    route_length(points, route) + penalty * waiting
+}
+
+/// Lowers `priority_cost` of a finished tour: first picks the starting point and direction,
+/// which leave the length unchanged, then moves priority points earlier one at a time while
+/// the detour is worth it.
+fn prioritize_route(points: &[(f64, f64)], route: &mut Vec<usize>, weights: &[f64], penalty: f64) {
+    let n = route.len();
+    if n < 2 {
+        return;
+    }
+    let cost = |r: &[usize]| priority_cost(points, r, weights, penalty);
+
+    let mut best = route.clone();
+    let mut best_cost = cost(route);
+    for reversed in [false, true] {
+        let mut candidate = route.clone();
+        if reversed {
+            candidate.reverse();
+        }
+        for _ in 0..n {
+            candidate.rotate_left(1);
+            let candidate_cost = cost(&candidate);
+            if candidate_cost < best_cost {
+                best_cost = candidate_cost;
+                best.clone_from(&candidate);
+            }
+        }
+    }
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for from in 1..n {
+            if weights[best[from]] == 0.0 {
+                continue;
+            }
+            for to in 0..from {
+                let mut candidate = best.clone();
+                let point = candidate.remove(from);
+                candidate.insert(to, point);
+                let candidate_cost = cost(&candidate);
+                if candidate_cost < best_cost - 1e-9 {
+                    best_cost = candidate_cost;
+                    best = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
     }
+    *route = best;
 }
 
 fn update_drawing_edges(model: &mut Model) {
-    model.animations.edge_animation_progress += EDGES_ANIMATION_SPEED;
-    if model.animations.edge_animation_progress >= NUM_COORDS as f32 {
-        model.animations.edge_animation_progress = NUM_COORDS as f32;
-        model.animations.solution_view_progress = 0.0;
-        model.state = ModelState::ViewingSolution;
+    model.animations.edge_animation_progress += model.pacing.edges;
+    if model.animations.edge_animation_progress >= model.num_coords as f32 {
+        finish_tour(model);
+    }
+}
+
+/// Shows the whole tour and saves the poster if one was asked for and not saved yet.
+fn finish_tour(model: &mut Model) {
+    model.animations.edge_animation_progress = model.num_coords as f32;
+    model.animations.solution_view_progress = 0.0;
+    model.state = ModelState::ViewingSolution;
+    if model.args.count_crossings {
+        model.crossings = tour_crossings(&model.coords, &model.current_tour);
+    }
+
+    if let (Some(path), false) = (&model.args.poster, model.poster_saved) {
+        let poster = render_poster(&model.coords, &model.current_tour, model.args.poster_scale);
+        match poster.save(path) {
+            Ok(()) => println!("Saved poster to {}", path.display()),
+            Err(e) => eprintln!("Failed to save poster to {}: {}", path.display(), e),
+        }
+        model.poster_saved = true;
     }
 }
 
 fn update_viewing_solution(model: &mut Model, update: Update) {
-    model.animations.solution_view_progress += update.since_last.as_secs_f32();
+    model.animations.solution_view_progress += model
+        .pacing
+        .view
+        .unwrap_or_else(|| update.since_last.as_secs_f32());
     if model.animations.solution_view_progress >= SOLUTION_VIEW_TIME {
         // Generate new random target coordinates
-        for i in 0..NUM_COORDS {
-            model.target_coords[i] = random_point();
+        for i in 0..model.num_coords {
+            if !model.fixed_coords {
+                model.target_coords[i] = random_point();
+            }
             model.animations.coord_animation_progress[i] = 0.0;
         }
         model.animations.edge_animation_progress = 0.0;
@@ -147,80 +1022,703 @@ fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
     draw.background().color(LINEN);
 
+    // The cells go stale as soon as the points start moving again.
+    if model.args.voronoi && (model.args.orbit || !matches!(model.state, ModelState::MovingCoords))
+    {
+        draw_voronoi(model, &draw);
+    }
+
+    if model.args.grid_overlay {
+        draw_grid(model, &draw);
+    }
+
     // Draw points
-    for coord in &model.coords {
-        draw.ellipse().xy(*coord).radius(5.0).color(BLACK);
+    for (coord, weight) in model.coords.iter().zip(&model.priorities) {
+        // Priority points grow with their weight.
+        let radius = 5.0 + 3.0 * *weight as f32;
+        draw.ellipse().xy(*coord).radius(radius).color(BLACK);
     }
     // In the view function, replace the edge drawing code with this:
     if matches!(
         model.state,
-        ModelState::DrawingEdges | ModelState::ViewingSolution
+        ModelState::DrawingEdges | ModelState::ViewingSolution | ModelState::Optimizing
     ) {
         let progress = model.animations.edge_animation_progress;
         if progress > 0.0 {
             let num_edges = progress.floor() as usize;
             let partial_progress = progress.fract();
+            // Measured over the whole tour so edges keep their colors as the rest draw in.
+            let longest = longest_edge(&model.coords, &model.current_tour);
 
-            // Draw complete edges
-            for i in 0..num_edges.min(NUM_COORDS) {
-                let start = model.coords[model.current_tour[i]];
-                let end = model.coords[model.current_tour[(i + 1) % NUM_COORDS]];
-                draw.line()
-                    .start(start)
-                    .end(end)
-                    .weight(2.0)
-                    .color(rgba(0.0, 0.0, 0.0, 0.5));
+            if model.args.mst != Some(MstMode::Only) {
+                // Draw complete edges
+                for i in 0..num_edges.min(model.num_coords) {
+                    draw_edge(model, &draw, i, 1.0, longest);
+                }
+
+                // Draw partial edge if in DrawingEdges state
+                if matches!(model.state, ModelState::DrawingEdges) && partial_progress > 0.0 {
+                    draw_edge(
+                        model,
+                        &draw,
+                        num_edges % model.num_coords,
+                        partial_progress,
+                        longest,
+                    );
+                }
             }
 
-            // Draw partial edge if in DrawingEdges state
-            if matches!(model.state, ModelState::DrawingEdges) && partial_progress > 0.0 {
-                let start = model.coords[model.current_tour[num_edges % NUM_COORDS]];
-                let end = model.coords[model.current_tour[(num_edges + 1) % NUM_COORDS]];
+            if model.args.mst.is_some() {
+                draw_mst(model, &draw, progress / model.num_coords as f32);
+            }
+        }
+    }
 
-                let actual_end = pt2(
-                    lerp(start.x, end.x, partial_progress),
-                    lerp(start.y, end.y, partial_progress),
-                );
+    if matches!(model.state, ModelState::ShowingTwoOpt) {
+        draw_two_opt(model, &draw);
+    }
 
-                draw.line()
-                    .start(start)
-                    .end(actual_end)
-                    .weight(2.0)
-                    .color(rgba(0.0, 0.0, 0.0, 0.5));
+    if model.args.matrix {
+        draw_distance_matrix(model, &draw, app.window_rect());
+    }
+
+    if model.args.count_crossings && matches!(model.state, ModelState::ViewingSolution) {
+        draw_crossings(model, &draw);
+    }
+
+    if model.args.edge_labels && matches!(model.state, ModelState::ViewingSolution) {
+        draw_edge_labels(model, &draw);
+    }
+
+    if model.args.show_temp
+        && matches!(
+            model.state,
+            ModelState::DrawingEdges | ModelState::ViewingSolution
+        )
+    {
+        draw_cooling(model, &draw, app.window_rect());
+    }
+
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        "1.25",
+        Corner::BottomLeft,
+        None,
+        LINEN.into_format(),
+    );
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        &stats_label(model),
+        Corner::BottomRight,
+        None,
+        LINEN.into_format(),
+    );
+
+    draw.to_frame(app, &frame).unwrap();
+}
+
+/// Draws the tour edge leaving position `i` up to `progress` of the way along, as a
+/// straight line or, under `--spline`, a curve.
+fn draw_edge(model: &Model, draw: &Draw, i: usize, progress: f32, longest: f32) {
+    let tour = &model.current_tour;
+    let n = tour.len();
+    let point = |offset: usize| model.coords[tour[(i + offset) % n]];
+    let color = if model.args.length_colors {
+        length_color(point(0).distance(point(1)) / longest.max(f32::EPSILON))
+    } else {
+        rgba(0.0, 0.0, 0.0, 0.5)
+    };
+
+    if !model.args.spline {
+        let (start, end) = (point(0), point(1));
+        let actual_end = lerp_point(start, end, progress);
+        draw.line()
+            .start(start)
+            .end(actual_end)
+            .weight(2.0)
+            .color(color);
+        return;
+    }
+
+    // The neighbours either side shape the curve so it joins the next edge smoothly.
+    let (p0, p1, p2, p3) = (point(n - 1), point(0), point(1), point(2));
+    let steps = ((SPLINE_SEGMENTS as f32 * progress).ceil() as usize).max(1);
+    let points = (0..=steps).map(|k| {
+        let t = progress * k as f32 / steps as f32;
+        catmull_rom(p0, p1, p2, p3, t)
+    });
+    draw.polyline().weight(2.0).points(points).color(color);
+}
+
+fn longest_edge(coords: &[Point2], tour: &[usize]) -> f32 {
+    (0..tour.len())
+        .map(|i| coords[tour[i]].distance(coords[tour[(i + 1) % tour.len()]]))
+        .fold(0.0, f32::max)
+}
+
+/// Green for the shortest edges through yellow to red for the longest, `t` from 0 to 1.
+fn length_color(t: f32) -> Rgba {
+    let t = t.clamp(0.0, 1.0);
+    rgba(
+        (2.0 * t).min(1.0),
+        (2.0 * (1.0 - t)).min(1.0) * 0.8,
+        0.0,
+        0.8,
+    )
+}
+
+/// Draws the first `progress` (0 to 1) of the spanning tree's edges in the order they were
+/// added, so it grows out from the first point as the tour is drawn.
+fn draw_mst(model: &Model, draw: &Draw, progress: f32) {
+    let shown = progress.clamp(0.0, 1.0) * model.mst.len() as f32;
+    for (k, &(a, b)) in model.mst.iter().enumerate() {
+        let edge_progress = (shown - k as f32).clamp(0.0, 1.0);
+        if edge_progress <= 0.0 {
+            break;
+        }
+        let (start, end) = (model.coords[a], model.coords[b]);
+        draw.line()
+            .start(start)
+            .end(start + (end - start) * edge_progress)
+            .weight(3.0)
+            .color(rgba(0.1, 0.4, 0.8, 0.7));
+    }
+}
+
+/// Edges of the minimum spanning tree of `coords` as index pairs, parent first, in the
+/// order Prim's algorithm adds them starting from point 0.
+fn minimum_spanning_tree(coords: &[Point2]) -> Vec<(usize, usize)> {
+    let n = coords.len();
+    let mut edges = Vec::with_capacity(n.saturating_sub(1));
+    if n == 0 {
+        return edges;
+    }
+
+    let mut in_tree = vec![false; n];
+    // Cheapest known connection from each point outside the tree: (distance, parent).
+    let mut nearest = vec![(f32::INFINITY, 0); n];
+    in_tree[0] = true;
+    for (i, coord) in coords.iter().enumerate().skip(1) {
+        nearest[i] = (coord.distance(coords[0]), 0);
+    }
+
+    for _ in 1..n {
+        let Some(next) = (0..n)
+            .filter(|&i| !in_tree[i])
+            .min_by(|&a, &b| nearest[a].0.total_cmp(&nearest[b].0))
+        else {
+            break;
+        };
+        in_tree[next] = true;
+        edges.push((nearest[next].1, next));
+        for (i, coord) in coords.iter().enumerate() {
+            let distance = coord.distance(coords[next]);
+            if !in_tree[i] && distance < nearest[i].0 {
+                nearest[i] = (distance, next);
             }
         }
     }
+    edges
+}
 
-    watermark(&draw);
-    tour_length_watermark(model, &draw);
+/// Point `t` of the way from `p1` to `p2` on the uniform Catmull-Rom curve through all four.
+fn catmull_rom(p0: Point2, p1: Point2, p2: Point2, p3: Point2, t: f32) -> Point2 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    ((p1 * 2.0)
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
 
-    draw.to_frame(app, &frame).unwrap();
+/// Grid lines every `--grid-spacing` pixels out from the center, with the axes picked out
+/// and labelled at each line.
+fn draw_grid(model: &Model, draw: &Draw) {
+    let half_width = OS_WINDOW_WIDTH as f32 / 2.0;
+    let half_height = OS_WINDOW_HEIGHT as f32 / 2.0;
+    let spacing = model
+        .args
+        .grid_spacing
+        .filter(|s| *s > 0.0)
+        .unwrap_or(OS_WINDOW_WIDTH as f32 / GRID_DIVISIONS as f32);
+
+    let steps = (half_width.max(half_height) / spacing) as i32;
+    for step in -steps..=steps {
+        let offset = step as f32 * spacing;
+        let (color, weight) = if step == 0 {
+            (rgba(0.0, 0.0, 0.0, 0.35), 1.5)
+        } else {
+            (rgba(0.0, 0.0, 0.0, 0.1), 1.0)
+        };
+        if offset.abs() <= half_width {
+            draw.line()
+                .start(pt2(offset, -half_height))
+                .end(pt2(offset, half_height))
+                .weight(weight)
+                .color(color);
+        }
+        if offset.abs() <= half_height {
+            draw.line()
+                .start(pt2(-half_width, offset))
+                .end(pt2(half_width, offset))
+                .weight(weight)
+                .color(color);
+        }
+
+        if step != 0 {
+            let label = format!("{}", offset.round());
+            let label_color = rgba(0.0, 0.0, 0.0, 0.4);
+            draw.text(&label)
+                .color(label_color)
+                .font_size(10)
+                .x_y(offset, -10.0);
+            draw.text(&label)
+                .color(label_color)
+                .font_size(10)
+                .x_y(-18.0, offset);
+        }
+    }
 }
 
-fn watermark(draw: &Draw) {
-    draw.text("1.25")
-        .color(rgba(0.0, 0.0, 0.0, 0.5))
-        .font_size(24)
-        .align_text_bottom()
-        .x_y(
-            -(OS_WINDOW_WIDTH as f32) / 2.0 + 40.0,
-            -(OS_WINDOW_HEIGHT as f32) / 2.0 + 110.0,
-        );
+/// The tour so far, with the edges the next 2-opt move removes in red and its
+/// replacements in green.
+fn draw_two_opt(model: &Model, draw: &Draw) {
+    let tour = &model.current_tour;
+    for i in 0..tour.len() {
+        draw.line()
+            .start(model.coords[tour[i]])
+            .end(model.coords[tour[(i + 1) % tour.len()]])
+            .weight(2.0)
+            .color(rgba(0.0, 0.0, 0.0, 0.5));
+    }
+
+    if let Some(next) = model.two_opt_moves.last() {
+        let (removed, added) = next.edges(tour);
+        for (edges, color) in [
+            (removed, rgba(0.9, 0.1, 0.1, 0.9)),
+            (added, rgba(0.1, 0.6, 0.2, 0.9)),
+        ] {
+            for (a, b) in edges {
+                draw.line()
+                    .start(model.coords[a])
+                    .end(model.coords[b])
+                    .weight(4.0)
+                    .color(color);
+            }
+        }
+    }
 }
 
-fn tour_length_watermark(model: &Model, draw: &Draw) {
+/// The solver, the crossings count when it's shown, the tour length once there is a tour,
+/// and the solve budget, stacked for the bottom-right corner.
+fn stats_label(model: &Model) -> String {
+    let solver = if model.args.optimize_live {
+        "2-opt (live)"
+    } else {
+        model.args.solver.name()
+    };
+    let mut lines = vec![solver.to_string()];
+    if model.args.count_crossings && matches!(model.state, ModelState::ViewingSolution) {
+        lines.push(format!("{} crossings", model.crossings.len()));
+    }
     if model.tour_length > 0.0 {
-        draw.text(&format!("{:.1}", model.tour_length))
-            .color(rgba(0.0, 0.0, 0.0, 0.5))
-            .font_size(24)
-            .align_text_bottom()
-            .x_y(
-                OS_WINDOW_WIDTH as f32 / 2.0 - 50.0,
-                -(OS_WINDOW_HEIGHT as f32) / 2.0 + 110.0,
-            );
+        lines.push(format!("{:.1}", model.tour_length));
+    }
+    lines.push(format!("{} ms", model.solve_budget_ms));
+    lines.join("\n")
+}
+
+fn draw_crossings(model: &Model, draw: &Draw) {
+    for crossing in &model.crossings {
+        draw.ellipse()
+            .xy(*crossing)
+            .radius(4.0)
+            .color(rgba(0.9, 0.1, 0.1, 0.9));
+    }
+}
+
+/// Graphs the last solve's cooling in the top right: temperature on a log scale in red,
+/// tour length in black. The graph is revealed in step with the edges being drawn.
+fn draw_cooling(model: &Model, draw: &Draw, rect: Rect) {
+    let samples = &model.cooling;
+    if samples.len() < 2 {
+        return;
+    }
+    let top_right = corner_point(rect, Corner::TopRight, vec2(20.0, 20.0));
+    let bottom_left = top_right - vec2(TEMPERATURE_GRAPH_WIDTH, TEMPERATURE_GRAPH_HEIGHT);
+    let revealed = model.animations.edge_animation_progress / model.num_coords as f32;
+    let shown = ((samples.len() as f32 * revealed.min(1.0)) as usize).max(2);
+
+    let (min_length, max_length) = samples.iter().fold((f64::MAX, f64::MIN), |(lo, hi), s| {
+        (lo.min(s.length), hi.max(s.length))
+    });
+    let log_range = (ANNEAL_START_TEMPERATURE / ANNEAL_END_TEMPERATURE).ln();
+    let point = |i: usize, value: f64| {
+        pt2(
+            bottom_left.x + TEMPERATURE_GRAPH_WIDTH * i as f32 / (samples.len() - 1) as f32,
+            bottom_left.y + TEMPERATURE_GRAPH_HEIGHT * value.clamp(0.0, 1.0) as f32,
+        )
+    };
+
+    draw.rect()
+        .xy((top_right + bottom_left) / 2.0)
+        .w_h(TEMPERATURE_GRAPH_WIDTH, TEMPERATURE_GRAPH_HEIGHT)
+        .color(rgba(1.0, 1.0, 1.0, 0.5))
+        .stroke(rgba(0.0, 0.0, 0.0, 0.5))
+        .stroke_weight(1.0);
+    draw.polyline()
+        .weight(1.5)
+        .points(
+            samples[..shown]
+                .iter()
+                .enumerate()
+                .map(|(i, s)| point(i, (s.temperature / ANNEAL_END_TEMPERATURE).ln() / log_range)),
+        )
+        .color(rgba(0.9, 0.1, 0.1, 0.9));
+    draw.polyline()
+        .weight(1.5)
+        .points(samples[..shown].iter().enumerate().map(|(i, s)| {
+            let range = (max_length - min_length).max(f64::EPSILON);
+            point(i, (s.length - min_length) / range)
+        }))
+        .color(rgba(0.0, 0.0, 0.0, 0.8));
+
+    let current = &samples[shown - 1];
+    draw.text(&format!("T = {:.2}", current.temperature))
+        .color(rgba(0.9, 0.1, 0.1, 0.9))
+        .font_size(12)
+        .left_justify()
+        .w(TEMPERATURE_GRAPH_WIDTH)
+        .xy(bottom_left + vec2(TEMPERATURE_GRAPH_WIDTH / 2.0, -12.0));
+}
+
+/// Each tour edge's length, nudged off the middle of the edge so short edges don't put
+/// their label on top of the points.
+fn draw_edge_labels(model: &Model, draw: &Draw) {
+    let tour = &model.current_tour;
+    let size = model.args.edge_label_size;
+    for i in 0..tour.len() {
+        let start = model.coords[tour[i]];
+        let end = model.coords[tour[(i + 1) % tour.len()]];
+        let normal = (end - start).perp().normalize_or_zero();
+        let position = (start + end) / 2.0 + normal * size as f32;
+        draw.text(&format!("{:.0}", start.distance(end)))
+            .color(rgba(0.0, 0.0, 0.0, 0.6))
+            .font_size(size)
+            .xy(position);
+    }
+}
+
+/// Points where two non-adjacent edges of the closed tour intersect.
+fn tour_crossings(coords: &[Point2], tour: &[usize]) -> Vec<Point2> {
+    let n = tour.len();
+    let edge = |i: usize| (coords[tour[i]], coords[tour[(i + 1) % n]]);
+    let mut crossings = Vec::new();
+    for i in 0..n {
+        for j in i + 2..n {
+            // The first and last edges share the starting point.
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            if let Some(p) = segment_intersection(edge(i), edge(j)) {
+                crossings.push(p);
+            }
+        }
+    }
+    crossings
+}
+
+/// Where the two segments cross, if they do.
+fn segment_intersection((p, p2): (Point2, Point2), (q, q2): (Point2, Point2)) -> Option<Point2> {
+    let r = p2 - p;
+    let s = q2 - q;
+    let denominator = r.perp_dot(s);
+    if denominator.abs() < f32::EPSILON {
+        return None; // Parallel
+    }
+    let t = (q - p).perp_dot(s) / denominator;
+    let u = (q - p).perp_dot(r) / denominator;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then(|| p + r * t)
+}
+
+/// Index of the nearest point to the center of each `VORONOI_CELL_SIZE` cell of the window.
+fn voronoi_cells(coords: &[Point2]) -> Vec<usize> {
+    let cols = OS_WINDOW_WIDTH / VORONOI_CELL_SIZE;
+    let rows = OS_WINDOW_HEIGHT / VORONOI_CELL_SIZE;
+    (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| voronoi_cell_center(row, col)))
+        .map(|center| {
+            coords
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    a.distance_squared(center)
+                        .total_cmp(&b.distance_squared(center))
+                })
+                .map_or(0, |(i, _)| i)
+        })
+        .collect()
+}
+
+fn voronoi_cell_center(row: u32, col: u32) -> Point2 {
+    let size = VORONOI_CELL_SIZE as f32;
+    pt2(
+        -(OS_WINDOW_WIDTH as f32) / 2.0 + (col as f32 + 0.5) * size,
+        -(OS_WINDOW_HEIGHT as f32) / 2.0 + (row as f32 + 0.5) * size,
+    )
+}
+
+/// Each backdrop cell in a pale hue picked by its nearest point.
+fn draw_voronoi(model: &Model, draw: &Draw) {
+    let cols = OS_WINDOW_WIDTH / VORONOI_CELL_SIZE;
+    let size = VORONOI_CELL_SIZE as f32;
+    for (i, nearest) in model.voronoi.iter().enumerate() {
+        let (row, col) = (i as u32 / cols, i as u32 % cols);
+        let hue = *nearest as f32 / model.num_coords as f32;
+        draw.rect()
+            .xy(voronoi_cell_center(row, col))
+            .w_h(size, size)
+            .color(hsl(hue, 0.45, 0.85));
     }
 }
 
+fn distance_matrix(coords: &[Point2]) -> Vec<Vec<f32>> {
+    coords
+        .iter()
+        .map(|a| coords.iter().map(|b| a.distance(*b)).collect())
+        .collect()
+}
+
+fn draw_distance_matrix(model: &Model, draw: &Draw, rect: Rect) {
+    let n = model.distance_matrix.len();
+    if n == 0 {
+        return;
+    }
+
+    let size = model.args.matrix_size;
+    let cell = size / n as f32;
+    let top_left = corner_point(rect, Corner::TopLeft, vec2(20.0, 20.0));
+    let max_distance = model
+        .distance_matrix
+        .iter()
+        .flatten()
+        .fold(0.0, |max: f32, d| max.max(*d));
+
+    // Near pairs are dark, far pairs fade into the background.
+    for (i, row) in model.distance_matrix.iter().enumerate() {
+        for (j, d) in row.iter().enumerate() {
+            let shade = if max_distance > 0.0 {
+                d / max_distance
+            } else {
+                0.0
+            };
+            draw.rect()
+                .x_y(
+                    top_left.x + (j as f32 + 0.5) * cell,
+                    top_left.y - (i as f32 + 0.5) * cell,
+                )
+                .w_h(cell, cell)
+                .color(rgba(shade, shade, shade, 0.8));
+        }
+    }
+
+    // Highlight the cells of the edges used by the tour.
+    for i in 0..model.current_tour.len() {
+        let a = model.current_tour[i];
+        let b = model.current_tour[(i + 1) % model.current_tour.len()];
+        for (row, col) in [(a, b), (b, a)] {
+            draw.rect()
+                .x_y(
+                    top_left.x + (col as f32 + 0.5) * cell,
+                    top_left.y - (row as f32 + 0.5) * cell,
+                )
+                .w_h(cell, cell)
+                .color(rgba(0.9, 0.1, 0.1, 0.9));
+        }
+    }
+
+    draw.rect()
+        .x_y(top_left.x + size / 2.0, top_left.y - size / 2.0)
+        .w_h(size, size)
+        .no_fill()
+        .stroke(BLACK)
+        .stroke_weight(1.0);
+}
+
+/// Renders the points, tour and convex hull without any HUD at `scale` times the window size.
+fn render_poster(coords: &[Point2], tour: &[usize], scale: u32) -> RgbaImage {
+    let scale = scale.max(1);
+    let linen: Srgb<u8> = LINEN;
+    let mut image = RgbaImage::from_pixel(
+        OS_WINDOW_WIDTH * scale,
+        OS_WINDOW_HEIGHT * scale,
+        Rgba([linen.red, linen.green, linen.blue, 255]),
+    );
+
+    // Window coordinates are centered with y up; image coordinates start top-left with y down.
+    let to_image = |p: Point2| {
+        pt2(
+            (p.x + OS_WINDOW_WIDTH as f32 / 2.0) * scale as f32,
+            (OS_WINDOW_HEIGHT as f32 / 2.0 - p.y) * scale as f32,
+        )
+    };
+    let s = scale as f32;
+
+    let hull = convex_hull(coords);
+    for i in 0..hull.len() {
+        let a = to_image(hull[i]);
+        let b = to_image(hull[(i + 1) % hull.len()]);
+        stroke_segment(&mut image, a, b, 1.5 * s, [0.0, 0.0, 0.0, 0.25]);
+    }
+
+    for i in 0..tour.len() {
+        let a = to_image(coords[tour[i]]);
+        let b = to_image(coords[tour[(i + 1) % tour.len()]]);
+        stroke_segment(&mut image, a, b, 3.0 * s, [0.0, 0.0, 0.0, 0.6]);
+    }
+
+    for coord in coords {
+        let c = to_image(*coord);
+        stroke_segment(&mut image, c, c, 10.0 * s, [0.0, 0.0, 0.0, 1.0]);
+    }
+
+    image
+}
+
+/// Blends a round-capped line of the given width into the image. A zero-length segment
+/// draws a dot.
+fn stroke_segment(image: &mut RgbaImage, a: Point2, b: Point2, width: f32, color: [f32; 4]) {
+    let radius = width / 2.0;
+    let min_x = (a.x.min(b.x) - radius).floor().max(0.0) as u32;
+    let min_y = (a.y.min(b.y) - radius).floor().max(0.0) as u32;
+    let max_x = ((a.x.max(b.x) + radius).ceil() as u32).min(image.width());
+    let max_y = ((a.y.max(b.y) + radius).ceil() as u32).min(image.height());
+
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let p = pt2(x as f32 + 0.5, y as f32 + 0.5);
+            let t = if length_squared > 0.0 {
+                ((p - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let distance = p.distance(a + ab * t);
+            // One pixel of anti-aliasing at the edge of the stroke.
+            let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                let alpha = color[3] * coverage;
+                let pixel = image.get_pixel_mut(x, y);
+                for c in 0..3 {
+                    let dst = pixel[c] as f32 / 255.0;
+                    pixel[c] = ((color[c] * alpha + dst * (1.0 - alpha)) * 255.0).round() as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Convex hull in counter-clockwise order (Andrew's monotone chain).
+fn convex_hull(points: &[Point2]) -> Vec<Point2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let cross = |o: Point2, a: Point2, b: Point2| (a - o).perp_dot(b - o);
+    let mut hull: Vec<Point2> = Vec::with_capacity(sorted.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &Point2>> = if pass == 0 {
+            Box::new(sorted.iter())
+        } else {
+            Box::new(sorted.iter().rev())
+        };
+        for p in ordered {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], *p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        // The last point of each chain is the first point of the next.
+        hull.pop();
+    }
+    hull
+}
+
+/// Reads `x,y` lines, or the `id x y` lines of a TSPLIB `NODE_COORD_SECTION`, skipping
+/// blank lines and `#` comments, and fits them to the window.
+fn load_coords(path: &Path) -> Result<Vec<Point2>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let lines: Vec<(usize, &str)> = contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    // A TSPLIB section runs until the next keyword, usually `EOF`.
+    let (lines, tsplib) = match lines.iter().position(|(_, l)| *l == "NODE_COORD_SECTION") {
+        Some(start) => {
+            let section = &lines[start + 1..];
+            let end = section
+                .iter()
+                .position(|(_, l)| l.starts_with(|c: char| c.is_ascii_alphabetic()))
+                .unwrap_or(section.len());
+            (&section[..end], true)
+        }
+        None => (&lines[..], false),
+    };
+
+    let points = lines
+        .iter()
+        .map(|&(number, line)| {
+            parse_coord(line, tsplib).ok_or_else(|| {
+                let expected = if tsplib { "`id x y`" } else { "`x,y`" };
+                format!("line {}: expected {}, got `{}`", number, expected, line)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if points.len() < 3 {
+        return Err(format!("need at least 3 points, found {}", points.len()));
+    }
+    Ok(fit_to_window(&points))
+}
+
+fn parse_coord(line: &str, tsplib: bool) -> Option<Point2> {
+    let fields: Vec<&str> = if tsplib {
+        line.split_whitespace().skip(1).collect()
+    } else {
+        line.split(',').map(str::trim).collect()
+    };
+    match fields[..] {
+        [x, y] => Some(pt2(x.parse().ok()?, y.parse().ok()?)),
+        _ => None,
+    }
+}
+
+/// Scales and centers `points`, keeping their aspect ratio, to fill the same middle two
+/// thirds of the window that random points land in.
+fn fit_to_window(points: &[Point2]) -> Vec<Point2> {
+    let min = points
+        .iter()
+        .fold(Vec2::splat(f32::INFINITY), |m, &p| m.min(p));
+    let max = points
+        .iter()
+        .fold(Vec2::splat(f32::NEG_INFINITY), |m, &p| m.max(p));
+    let extent = vec2(OS_WINDOW_WIDTH as f32, OS_WINDOW_HEIGHT as f32) * 2.0 / 3.0;
+    let scale = (extent / (max - min).max(Vec2::splat(f32::EPSILON))).min_element();
+    let center = (min + max) / 2.0;
+    points.iter().map(|&p| (p - center) * scale).collect()
+}
+
 fn random_point() -> Point2 {
     let x = random_range(
         -(OS_WINDOW_WIDTH as f32) / 3.0,
@@ -232,7 +1730,3 @@ fn random_point() -> Point2 {
     );
     pt2(x, y)
 }
-
-fn lerp(start: f32, end: f32, t: f32) -> f32 {
-    start + (end - start) * t
-}
@@ -1,16 +1,413 @@
+use clap::{Parser, ValueEnum};
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::{attach_capture_with_state, CaptureState};
+use nannou_genuary_2025::common::watermark::{draw_watermark, Corner};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 
 const OS_WINDOW_WIDTH: u32 = 800;
 const OS_WINDOW_HEIGHT: u32 = 800;
+const MORPH_HOLD_SECONDS: f32 = 3.0; // Time spent at each symmetry in `--morph-symmetry`
+const MORPH_TRANSITION_SECONDS: f32 = 2.0; // Time spent easing to the next one
+const WARP_RADIUS: f32 = 400.0; // Distance from the warp center at which the swirl fades out
+const WARP_SEGMENTS: usize = 16; // Pieces each line is split into so it can bend
+const CORE_RADIUS: f32 = 0.2; // Size of the `--core` motif as a fraction of the pattern radius
+const CORE_LAYERS: usize = 4; // Polygons stacked in the nested core
+const BLOOM_LEVELS: usize = 3; // Halvings of the bright parts, each blurrier than the last, added back together
+const BURST_FRAMES: u64 = 60; // Frames between particle bursts, a second at 60fps
+
+// Weights the source by its own alpha and adds it onto the destination, so overlapping
+// translucent strokes brighten instead of converging on the stroke color.
+const BLEND_ADDITIVE: wgpu::BlendComponent = wgpu::BlendComponent {
+    src_factor: wgpu::BlendFactor::SrcAlpha,
+    dst_factor: wgpu::BlendFactor::One,
+    operation: wgpu::BlendOperation::Add,
+};
+
+// Takes the source away from the destination, clamping at black.
+const BLEND_SUBTRACT: wgpu::BlendComponent = wgpu::BlendComponent {
+    src_factor: wgpu::BlendFactor::One,
+    dst_factor: wgpu::BlendFactor::One,
+    operation: wgpu::BlendOperation::ReverseSubtract,
+};
+
+// Scales the destination by the source color.
+const BLEND_MULTIPLY: wgpu::BlendComponent = wgpu::BlendComponent {
+    src_factor: wgpu::BlendFactor::Zero,
+    dst_factor: wgpu::BlendFactor::Src,
+    operation: wgpu::BlendOperation::Add,
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Kaleidoscope using nannou")]
+struct Args {
+    /// Blend the glow lines, particles and overlay additively
+    #[arg(long)]
+    additive: bool,
+
+    /// Build the overlay from one wedge repeated around the circle for exact symmetry
+    #[arg(long)]
+    sym_overlay: bool,
+
+    /// Comma-separated symmetry counts to morph between smoothly, e.g. `4,8,6`
+    #[arg(long, value_delimiter = ',')]
+    morph_symmetry: Vec<usize>,
+
+    /// Resume from a state file saved with the S key
+    #[arg(long)]
+    restore: Option<PathBuf>,
+
+    /// Seed for the particle bursts (random if not given)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Swirl everything around `--warp-center` by up to this many radians (0 disables)
+    #[arg(long, default_value_t = 0.0)]
+    warp: f32,
+
+    /// Center of the swirl as `x,y`, relative to the middle of the window
+    #[arg(long, value_delimiter = ',', default_values_t = [0.0, 0.0])]
+    warp_center: Vec<f32>,
+
+    /// Sideways push on each particle around its system's origin, making bursts spiral
+    #[arg(long, default_value_t = 0.0)]
+    orbital: f32,
+
+    /// Start of a two-color hue gradient, in degrees, used instead of the full color wheel
+    #[arg(long, requires = "hue_to")]
+    hue_from: Option<f32>,
+
+    /// End of the `--hue-from` gradient, in degrees
+    #[arg(long, requires = "hue_from")]
+    hue_to: Option<f32>,
+
+    /// Hold the colors where they start instead of cycling them, leaving only the motion
+    #[arg(long)]
+    static_hue: bool,
+
+    /// Positions each particle remembers and draws as a fading trail (1 draws plain dots)
+    #[arg(long, default_value_t = 1)]
+    trail_len: usize,
+
+    /// Motif drawn at the center, on top of everything else
+    #[arg(long, value_enum)]
+    core: Option<CoreShape>,
+
+    /// Comma-separated layers to draw
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Layer::All])]
+    layers: Vec<Layer>,
+
+    /// Add a blurred glow around the bright parts of the scene
+    #[arg(long)]
+    bloom: bool,
+
+    /// Brightness (0-1) the scene has to pass before it blooms
+    #[arg(long, default_value_t = 0.2)]
+    bloom_threshold: f32,
+
+    /// Strength of the bloom added back over the scene. A blend can only darken the bloom,
+    /// so above 1 it's added whole-number times at a lower gain, e.g. 2.5 as three passes
+    /// at 0.83
+    #[arg(long, default_value_t = 1.0)]
+    bloom_intensity: f32,
+}
+
+/// The bright parts of the scene, cut out at full size and then halved `BLOOM_LEVELS`
+/// times. Each halving averages 2x2 texels, so the levels blur further and further out,
+/// and added back over the window together they make the glow.
+struct Bloom {
+    textures: Vec<wgpu::Texture>, // The full-size cut-out, then each level
+    renderers: Vec<nannou::draw::Renderer>,
+    draw: Draw,
+}
+
+impl Bloom {
+    fn new(window: &Window) -> Self {
+        let device = window.device();
+        let textures: Vec<wgpu::Texture> = (0..=BLOOM_LEVELS)
+            .map(|level| {
+                // A unorm format clamps the thresholding at black instead of going negative.
+                wgpu::TextureBuilder::new()
+                    .size([
+                        (OS_WINDOW_WIDTH >> level).max(1),
+                        (OS_WINDOW_HEIGHT >> level).max(1),
+                    ])
+                    .usage(
+                        wgpu::TextureUsages::RENDER_ATTACHMENT
+                            | wgpu::TextureUsages::TEXTURE_BINDING,
+                    )
+                    .sample_count(1)
+                    .format(wgpu::TextureFormat::Rgba8UnormSrgb)
+                    .build(device)
+            })
+            .collect();
+        let renderers = textures
+            .iter()
+            .map(|texture| {
+                nannou::draw::RendererBuilder::new()
+                    .build_from_texture_descriptor(device, texture.descriptor())
+            })
+            .collect();
+        Bloom {
+            textures,
+            renderers,
+            draw: Draw::new(),
+        }
+    }
+
+    /// The blurred levels, to be added over the window.
+    fn levels(&self) -> &[wgpu::Texture] {
+        &self.textures[1..]
+    }
+
+    /// Renders the bright parts of `model`'s scene and halves them down into the levels.
+    fn render(&mut self, window: &Window, model: &Model) {
+        let device = window.device();
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("bloom"),
+        });
+
+        self.draw.reset();
+        self.draw.background().color(BLACK);
+        let glow = if model.args.additive {
+            self.draw.color_blend(BLEND_ADDITIVE)
+        } else {
+            self.draw.clone()
+        };
+        draw_scene(model, &glow);
+
+        let threshold = model.args.bloom_threshold.clamp(0.0, 1.0);
+        self.draw
+            .color_blend(BLEND_SUBTRACT)
+            .rect()
+            .w_h(OS_WINDOW_WIDTH as f32, OS_WINDOW_HEIGHT as f32)
+            .color(rgb(threshold, threshold, threshold));
+        // Shared between the levels so adding them all back keeps the overall strength.
+        let gain = bloom_gain(model.args.bloom_intensity).1 / BLOOM_LEVELS as f32;
+        self.draw
+            .color_blend(BLEND_MULTIPLY)
+            .rect()
+            .w_h(OS_WINDOW_WIDTH as f32, OS_WINDOW_HEIGHT as f32)
+            .color(rgb(gain, gain, gain));
+        self.renderers[0].render_to_texture(device, &mut encoder, &self.draw, &self.textures[0]);
+
+        for level in 1..self.textures.len() {
+            let [width, height] = self.textures[level].size();
+            self.draw.reset();
+            self.draw
+                .sampler(bloom_sampler())
+                .texture(&self.textures[level - 1])
+                .w_h(width as f32, height as f32);
+            self.renderers[level].render_to_texture(
+                device,
+                &mut encoder,
+                &self.draw,
+                &self.textures[level],
+            );
+        }
+
+        window.queue().submit(Some(encoder.finish()));
+    }
+}
+
+/// Bilinear, so halving a texture averages each 2x2 block and stretching one back is smooth.
+fn bloom_sampler() -> wgpu::SamplerDescriptor<'static> {
+    wgpu::SamplerBuilder::new()
+        .mag_filter(wgpu::FilterMode::Linear)
+        .min_filter(wgpu::FilterMode::Linear)
+        .into_descriptor()
+}
+
+/// Splits `intensity` into a number of times to add the bloom and a gain of at most 1 to
+/// render it with, since a color can't scale it up.
+fn bloom_gain(intensity: f32) -> (usize, f32) {
+    let passes = intensity.max(0.0).ceil().max(1.0);
+    (passes as usize, intensity.max(0.0) / passes)
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Layer {
+    All,
+    /// The shimmering lines behind everything
+    Lines,
+    /// The particle bursts
+    Particles,
+    /// The kaleidoscopic overlay
+    Overlay,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CoreShape {
+    /// A single filled polygon with one corner per arm
+    Polygon,
+    /// Shrinking polygons turning alternately one way and the other
+    Nested,
+}
+
+/// A radial swirl applied to everything drawn, strongest at its center and fading to
+/// nothing at `WARP_RADIUS`.
+struct Warp {
+    amount: f32,
+    center: Point2,
+}
+
+impl Warp {
+    fn apply(&self, point: Point2) -> Point2 {
+        let offset = point - self.center;
+        let falloff = (1.0 - offset.length() / WARP_RADIUS).max(0.0);
+        self.center + offset.rotate(self.amount * falloff * falloff)
+    }
+
+    /// Draws a straight line bent by the warp.
+    fn line(&self, draw: &Draw, start: Point2, end: Point2, color: Hsla, weight: f32) {
+        if self.amount == 0.0 {
+            draw.line()
+                .start(start)
+                .end(end)
+                .color(color)
+                .stroke_weight(weight);
+            return;
+        }
+
+        let points = (0..=WARP_SEGMENTS)
+            .map(|i| self.apply(start.lerp(end, i as f32 / WARP_SEGMENTS as f32)));
+        draw.polyline()
+            .stroke_weight(weight)
+            .points(points)
+            .color(color);
+    }
+}
+
+/// The parts of `Model` that determine a frame, written alongside a screenshot so the
+/// moment can be picked up again with `--restore`. The particles aren't written out; the
+/// seed, frame and the symmetry and hue the burst started with are enough to replay them.
+struct SavedState {
+    time: f32,
+    pulse_phase: f32,
+    color_shift: f32,
+    symmetry: f32,
+    seed: u64,
+    frame: u64,
+    burst_symmetry: f32,
+    burst_color_shift: f32,
+}
+
+impl SavedState {
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let contents = format!(
+            "time={}\npulse_phase={}\ncolor_shift={}\nsymmetry={}\nseed={}\nframe={}\n\
+             burst_symmetry={}\nburst_color_shift={}\n",
+            self.time,
+            self.pulse_phase,
+            self.color_shift,
+            self.symmetry,
+            self.seed,
+            self.frame,
+            self.burst_symmetry,
+            self.burst_color_shift
+        );
+        std::fs::write(path, contents)
+    }
+
+    fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut state = SavedState {
+            time: 0.0,
+            pulse_phase: 0.0,
+            color_shift: 0.0,
+            symmetry: 6.0,
+            seed: 0,
+            frame: 0,
+            burst_symmetry: 6.0,
+            burst_color_shift: 0.0,
+        };
+        let (mut seed, mut frame) = (None, None);
+        for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("expected `key=value`, got `{}`", line))?;
+            let (key, value) = (key.trim(), value.trim());
+            let bad = |e: &dyn std::fmt::Display| format!("bad value for {}: {}", key, e);
+            let float = || value.parse::<f32>().map_err(|e| bad(&e));
+            let int = || value.parse::<u64>().map_err(|e| bad(&e));
+            match key {
+                "time" => state.time = float()?,
+                "pulse_phase" => state.pulse_phase = float()?,
+                "color_shift" => state.color_shift = float()?,
+                "symmetry" => state.symmetry = float()?,
+                "seed" => seed = Some(int()?),
+                "frame" => frame = Some(int()?),
+                "burst_symmetry" => state.burst_symmetry = float()?,
+                "burst_color_shift" => state.burst_color_shift = float()?,
+                other => return Err(format!("unknown key `{}`", other)),
+            }
+        }
+        // Without these the particles can't be replayed, so the frame can't be either.
+        state.seed = seed.ok_or("missing `seed`")?;
+        state.frame = frame.ok_or("missing `frame`")?;
+        Ok(state)
+    }
+}
 
 struct Model {
     time: f32,
+    time_offset: f32, // Added to the app's clock so a restored frame carries on from its time
     num_points: usize,
+    symmetry: f32, // Continuous arm count; `num_points` is this rounded
     radius: f32,
     pulse_phase: f32,
     rotation_speed: f32,
     color_shift: f32,
     particle_systems: Vec<ParticleSystem>,
+    seed: u64,
+    frame: u64, // Frames stepped so far, which time the bursts so a seed replays exactly
+    burst_symmetry: f32, // `symmetry` when the current burst started
+    burst_color_shift: f32, // `color_shift` when the current burst started
+    warp: Warp,
+    bloom: Option<Bloom>,
+    args: Args,
+}
+
+impl Model {
+    /// Replaces the particle systems with a fresh burst at each arm, the `generation`th.
+    fn burst(&mut self, generation: u64) {
+        self.particle_systems.clear();
+
+        // Create new particle systems at symmetrical points
+        for (angle, _) in arm_angles(self.burst_symmetry) {
+            let radius = self.radius * 0.5;
+            let origin = pt2(angle.cos() * radius, angle.sin() * radius);
+            let hue = self.hue(self.burst_color_shift + angle / TAU);
+            let color = hsla(hue, 0.5, 0.5, 1.0);
+            self.particle_systems
+                .push(ParticleSystem::new(origin, color, self.seed, generation));
+        }
+    }
+
+    fn step_particles(&mut self) {
+        for system in &mut self.particle_systems {
+            system.update(self.time, self.args.orbital, self.args.trail_len);
+        }
+    }
+
+    fn shows(&self, layer: Layer) -> bool {
+        self.args.layers.contains(&Layer::All) || self.args.layers.contains(&layer)
+    }
+
+    /// Hue (in turns) for position `t` around the color wheel. With `--hue-from`/`--hue-to`
+    /// the wheel is swapped for a gradient that runs out to `--hue-to` and back, so it wraps
+    /// as smoothly as the wheel does.
+    fn hue(&self, t: f32) -> f32 {
+        match (self.args.hue_from, self.args.hue_to) {
+            (Some(from), Some(to)) => {
+                let there_and_back = 1.0 - (2.0 * t.rem_euclid(1.0) - 1.0).abs();
+                (from + (to - from) * there_and_back).rem_euclid(360.0) / 360.0
+            }
+            _ => t.rem_euclid(1.0),
+        }
+    }
 }
 
 struct Particle {
@@ -19,40 +416,57 @@ struct Particle {
     life: f32,
     max_life: f32,
     color: Hsla,
+    trail: VecDeque<Point2>, // Recent positions, oldest first, ending at `position`
 }
 
 struct ParticleSystem {
     particles: Vec<Particle>,
     origin: Point2,
     color: Hsla,
+    rng: StdRng,
 }
 
 impl ParticleSystem {
-    fn new(origin: Point2, color: Hsla) -> Self {
+    /// `generation` counts the resets so far, giving each burst its own stream.
+    fn new(origin: Point2, color: Hsla, seed: u64, generation: u64) -> Self {
+        let position = ((origin.x.to_bits() as u64) << 32) | origin.y.to_bits() as u64;
+        let seed = seed
+            ^ position.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ generation.wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
         ParticleSystem {
             particles: Vec::new(),
             origin,
             color,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
-    fn update(&mut self, _time: f32) {
+    /// `orbital` is the tangential acceleration pulling particles round the origin, and
+    /// `trail_len` how many positions each particle keeps.
+    fn update(&mut self, _time: f32, orbital: f32, trail_len: usize) {
         // Remove dead particles
         self.particles.retain(|p| p.life > 0.0);
 
         // Update existing particles
         for particle in &mut self.particles {
+            let offset = particle.position - self.origin;
+            particle.velocity += offset.perp().normalize_or_zero() * orbital;
             particle.position += particle.velocity;
             particle.life -= 1.0;
             particle.velocity *= 0.98; // Add drag
+
+            particle.trail.push_back(particle.position);
+            while particle.trail.len() > trail_len.max(1) {
+                particle.trail.pop_front();
+            }
         }
 
         // Add new particles with symmetrical distribution
-        if random_f32() < 0.3 {
-            let angle = random_f32() * TAU;
-            let speed = random_range(0.5, 2.0);
+        if self.rng.gen::<f32>() < 0.3 {
+            let angle = self.rng.gen_range(0.0..TAU);
+            let speed = self.rng.gen_range(0.5..2.0);
             let velocity = vec2(angle.cos() * speed, angle.sin() * speed);
-            let life = random_range(50.0, 150.0);
+            let life = self.rng.gen_range(50.0..150.0);
 
             self.particles.push(Particle {
                 position: self.origin,
@@ -60,11 +474,12 @@ impl ParticleSystem {
                 life,
                 max_life: life,
                 color: self.color,
+                trail: VecDeque::from([self.origin]),
             });
         }
     }
 
-    fn draw(&self, draw: &Draw) {
+    fn draw(&self, draw: &Draw, warp: &Warp) {
         for particle in &self.particles {
             let alpha = particle.life / particle.max_life;
             let color = hsla(
@@ -74,8 +489,21 @@ impl ParticleSystem {
                 alpha,
             );
 
+            // The trail thins out towards its oldest end.
+            let segments = particle.trail.len() - 1;
+            for (k, (start, end)) in particle
+                .trail
+                .iter()
+                .zip(particle.trail.iter().skip(1))
+                .enumerate()
+            {
+                let mut faded = color;
+                faded.alpha *= (k + 1) as f32 / (segments + 1) as f32;
+                warp.line(draw, *start, *end, faded, 1.5);
+            }
+
             draw.ellipse()
-                .xy(particle.position)
+                .xy(warp.apply(particle.position))
                 .w_h(3.0, 3.0)
                 .color(color);
         }
@@ -87,50 +515,128 @@ fn main() {
 }
 
 fn model(app: &App) -> Model {
-    app.new_window()
-        .size(OS_WINDOW_WIDTH, OS_WINDOW_HEIGHT)
-        .view(view)
-        .build()
-        .unwrap();
+    let args = Args::parse();
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let window = attach_capture_with_state(
+        app.new_window()
+            .size(OS_WINDOW_WIDTH, OS_WINDOW_HEIGHT)
+            .view(view),
+    )
+    .build()
+    .unwrap();
 
-    Model {
+    let warp = Warp {
+        amount: args.warp,
+        center: pt2(
+            args.warp_center.first().copied().unwrap_or(0.0),
+            args.warp_center.get(1).copied().unwrap_or(0.0),
+        ),
+    };
+
+    let mut model = Model {
         time: 0.0,
+        time_offset: 0.0,
         num_points: 6,
+        symmetry: 6.0,
         radius: 200.0,
         pulse_phase: 0.0,
         rotation_speed: 1.0,
         color_shift: 0.0,
         particle_systems: Vec::new(),
+        seed,
+        frame: 0,
+        burst_symmetry: 6.0,
+        burst_color_shift: 0.0,
+        warp,
+        bloom: None,
+        args,
+    };
+    if model.args.bloom {
+        model.bloom = app.window(window).map(|window| Bloom::new(&window));
+    }
+
+    if let Some(path) = &model.args.restore {
+        match SavedState::load(path) {
+            Ok(state) => {
+                model.time = state.time;
+                model.time_offset = state.time;
+                model.pulse_phase = state.pulse_phase;
+                model.color_shift = state.color_shift;
+                model.symmetry = state.symmetry;
+                model.num_points = state.symmetry.round() as usize;
+                model.seed = state.seed;
+                model.burst_symmetry = state.burst_symmetry;
+                model.burst_color_shift = state.burst_color_shift;
+
+                // The burst in flight started on the last frame that was a multiple of
+                // `BURST_FRAMES` and has been stepped once on every frame since.
+                if let Some(last) = state.frame.checked_sub(1) {
+                    model.burst(last / BURST_FRAMES);
+                    for _ in 0..last % BURST_FRAMES {
+                        model.step_particles();
+                    }
+                }
+                model.frame = state.frame;
+            }
+            Err(e) => eprintln!(
+                "Warning: couldn't restore {} ({}), starting fresh",
+                path.display(),
+                e
+            ),
+        }
+    }
+    println!("Particle seed: {}", model.seed);
+    model
+}
+
+impl CaptureState for Model {
+    /// The S key's screenshot gets a `.txt` of the same name for `--restore`.
+    fn save_state(&self, image: &Path) {
+        let path = image.with_extension("txt");
+        let state = SavedState {
+            time: self.time,
+            pulse_phase: self.pulse_phase,
+            color_shift: self.color_shift,
+            symmetry: self.symmetry,
+            seed: self.seed,
+            frame: self.frame,
+            burst_symmetry: self.burst_symmetry,
+            burst_color_shift: self.burst_color_shift,
+        };
+        match state.save(&path) {
+            Ok(()) => println!("Saved {}", path.display()),
+            Err(e) => eprintln!("Failed to write {}: {}", path.display(), e),
+        }
     }
 }
 
 fn update(app: &App, model: &mut Model, _update: Update) {
-    model.time = app.time;
+    model.time = app.time + model.time_offset;
     model.pulse_phase += 0.02;
-    model.color_shift += 0.005;
+    if !model.args.static_hue {
+        model.color_shift += 0.005;
+    }
     model.rotation_speed = 1.0 + (model.time * 0.1).sin() * 0.5;
 
-    // Update particle systems
-    for system in &mut model.particle_systems {
-        system.update(model.time);
+    if !model.args.morph_symmetry.is_empty() {
+        model.symmetry = morphed_symmetry(&model.args.morph_symmetry, model.time);
+        model.num_points = model.symmetry.round() as usize;
     }
 
+    model.step_particles();
+
     // Periodically reset particle systems
-    if model.time.floor() != (model.time - _update.since_last.as_secs_f32()).floor() {
-        model.particle_systems.clear();
+    if model.frame.is_multiple_of(BURST_FRAMES) {
+        model.burst_symmetry = model.symmetry;
+        model.burst_color_shift = model.color_shift;
+        model.burst(model.frame / BURST_FRAMES);
+    }
 
-        // Create new particle systems at symmetrical points
-        for i in 0..model.num_points {
-            let angle = (i as f32 / model.num_points as f32) * TAU;
-            let radius = model.radius * 0.5;
-            let origin = pt2(angle.cos() * radius, angle.sin() * radius);
-            let hue = (model.color_shift + i as f32 / model.num_points as f32) % 1.0;
-            let color = hsla(hue, 0.5, 0.5, 1.0);
+    model.frame += 1;
 
-            model
-                .particle_systems
-                .push(ParticleSystem::new(origin, color));
-        }
+    if let Some(mut bloom) = model.bloom.take() {
+        bloom.render(&app.main_window(), model);
+        model.bloom = Some(bloom);
     }
 }
 
@@ -138,24 +644,90 @@ fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
     draw.background().color(BLACK);
 
+    // Everything but the background and watermark goes through the glow draw.
+    let glow = if model.args.additive {
+        draw.color_blend(BLEND_ADDITIVE)
+    } else {
+        draw.clone()
+    };
+
+    draw_scene(model, &glow);
+
+    if let Some(bloom) = &model.bloom {
+        let (passes, _) = bloom_gain(model.args.bloom_intensity);
+        let composite = draw.color_blend(BLEND_ADDITIVE).sampler(bloom_sampler());
+        for level in bloom.levels() {
+            for _ in 0..passes {
+                composite
+                    .texture(level)
+                    .w_h(OS_WINDOW_WIDTH as f32, OS_WINDOW_HEIGHT as f32);
+            }
+        }
+    }
+
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        "1.26",
+        Corner::BottomLeft,
+        None,
+        BLACK.into_format(),
+    );
+    draw.to_frame(app, &frame).unwrap();
+}
+
+/// Everything but the background and watermark.
+fn draw_scene(model: &Model, glow: &Draw) {
+    // Draw shimmering background patterns
+    if model.shows(Layer::Lines) {
+        draw_shimmer(model, glow);
+    }
+
+    // Draw particle systems
+    if model.shows(Layer::Particles) {
+        for system in &model.particle_systems {
+            system.draw(glow, &model.warp);
+        }
+    }
+
+    // Draw kaleidoscopic overlay
+    if model.shows(Layer::Overlay) {
+        let overlay = if model.args.sym_overlay {
+            symmetric_overlay_lines(model)
+        } else {
+            overlay_lines(model)
+        };
+        for (start, end, alpha) in overlay {
+            let color = hsla(model.hue(model.color_shift), 0.5, 0.5, alpha);
+            model.warp.line(glow, start, end, color, 1.0);
+        }
+    }
+
+    if let Some(shape) = model.args.core {
+        draw_core(model, glow, shape);
+    }
+}
+
+/// Rings of glowing lines joining the arms, pulsing and turning at staggered phases.
+fn draw_shimmer(model: &Model, draw: &Draw) {
     let center = pt2(0.0, 0.0);
     let pulse = (model.pulse_phase.sin() * 0.2 + 1.0) * 0.5;
 
-    // Draw shimmering background patterns
     for i in 0..8 {
         let phase = model.time * model.rotation_speed + i as f32 * PI / 4.0;
         let scale = (1.0 - (i as f32 * 0.1)) * pulse;
-        let hue = (model.color_shift + i as f32 / 8.0) % 1.0;
+        let hue = model.hue(model.color_shift + i as f32 / 8.0);
 
-        for j in 0..model.num_points {
-            let angle = (j as f32 / model.num_points as f32) * TAU + phase;
+        let arms = arm_angles(model.symmetry);
+        for (j, (angle, _)) in arms.iter().enumerate() {
+            let angle = angle + phase;
             let point = center
                 + vec2(
                     angle.cos() * model.radius * scale,
                     angle.sin() * model.radius * scale,
                 );
 
-            let next_angle = ((j + 1) as f32 / model.num_points as f32) * TAU + phase;
+            let next_angle = arms.get(j + 1).map_or(TAU, |(a, _)| *a) + phase;
             let next_point = center
                 + vec2(
                     next_angle.cos() * model.radius * scale,
@@ -167,51 +739,191 @@ fn view(app: &App, model: &Model, frame: Frame) {
                 let alpha = 0.2 - (k as f32 * 0.05);
                 let weight = 2.0 + (k as f32 * 2.0);
 
-                draw.line()
-                    .start(point)
-                    .end(next_point)
-                    .color(hsla(hue, 0.5, 0.5, alpha))
-                    .stroke_weight(weight);
+                model
+                    .warp
+                    .line(draw, point, next_point, hsla(hue, 0.5, 0.5, alpha), weight);
             }
         }
     }
+}
 
-    // Draw particle systems
-    for system in &model.particle_systems {
-        system.draw(&draw);
+/// The central motif, with a corner per arm, turning with the pattern and taking its hue.
+fn draw_core(model: &Model, draw: &Draw, shape: CoreShape) {
+    let sides = model.num_points.max(3);
+    let radius = model.radius * CORE_RADIUS;
+    let rotation = model.time * model.rotation_speed;
+    let hue = model.hue(model.color_shift);
+    let polygon = |radius: f32, rotation: f32| {
+        (0..sides)
+            .map(|i| {
+                let angle = rotation + i as f32 / sides as f32 * TAU;
+                model.warp.apply(pt2(angle.cos(), angle.sin()) * radius)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    match shape {
+        CoreShape::Polygon => {
+            let points = polygon(radius, rotation);
+            draw.polygon()
+                .points(points.iter().copied())
+                .color(hsla(hue, 0.5, 0.3, 0.6));
+            draw.polyline()
+                .weight(2.0)
+                .points_closed(points)
+                .color(hsla(hue, 0.6, 0.6, 0.9));
+        }
+        CoreShape::Nested => {
+            for layer in 0..CORE_LAYERS {
+                let t = layer as f32 / CORE_LAYERS as f32;
+                let direction = if layer.is_multiple_of(2) { 1.0 } else { -1.0 };
+                let points = polygon(radius * (1.0 - t), rotation * direction * (1.0 + t));
+                draw.polyline()
+                    .weight(2.0)
+                    .points_closed(points)
+                    .color(hsla(model.hue(model.color_shift + t / 2.0), 0.6, 0.6, 0.9));
+            }
+        }
     }
+}
 
-    // Draw kaleidoscopic overlay
-    let overlay_points: Vec<_> = (0..model.num_points * 2)
+/// Angles of `symmetry` evenly spaced arms with the weight each is drawn at. A fractional
+/// symmetry adds one partial arm that grows out of the first as the others spread apart
+/// to make room, so the pattern changes continuously as the count does.
+fn arm_angles(symmetry: f32) -> Vec<(f32, f32)> {
+    let symmetry = symmetry.max(1.0);
+    let count = symmetry.ceil() as usize;
+    let partial = symmetry.fract();
+    (0..count)
         .map(|i| {
-            let angle = (i as f32 / (model.num_points * 2) as f32) * TAU;
+            let weight = if i == count - 1 && partial > 0.0 {
+                partial
+            } else {
+                1.0
+            };
+            (i as f32 / symmetry * TAU, weight)
+        })
+        .collect()
+}
+
+/// Symmetry at `time` while holding on each count in `pattern` in turn and easing
+/// between them.
+fn morphed_symmetry(pattern: &[usize], time: f32) -> f32 {
+    let cycle = MORPH_HOLD_SECONDS + MORPH_TRANSITION_SECONDS;
+    let step = (time / cycle) as usize;
+    let from = pattern[step % pattern.len()].max(1) as f32;
+    let to = pattern[(step + 1) % pattern.len()].max(1) as f32;
+    let t = ((time % cycle - MORPH_HOLD_SECONDS) / MORPH_TRANSITION_SECONDS).clamp(0.0, 1.0);
+    let eased = t * t * (3.0 - 2.0 * t);
+    from + (to - from) * eased
+}
+
+/// Every pair of overlay points joined by a line, as `(start, end, alpha)`.
+fn overlay_lines(model: &Model) -> Vec<(Point2, Point2, f32)> {
+    let overlay_points: Vec<_> = arm_angles(model.symmetry * 2.0)
+        .into_iter()
+        .map(|(angle, weight)| {
             let r = model.radius * 0.3 * (1.0 + (model.time * 2.0 + angle * 2.0).sin() * 0.1);
-            center + vec2(angle.cos() * r, angle.sin() * r)
+            (pt2(angle.cos() * r, angle.sin() * r), weight)
         })
         .collect();
 
+    let mut lines = Vec::new();
     for i in 0..overlay_points.len() {
         for j in i + 1..overlay_points.len() {
+            let (start, start_weight) = overlay_points[i];
+            let (end, end_weight) = overlay_points[j];
             let alpha = ((model.time + i as f32 * 0.1).sin() * 0.15 + 0.15).max(0.0);
-            draw.line()
-                .start(overlay_points[i])
-                .end(overlay_points[j])
-                .color(hsla(model.color_shift, 0.5, 0.5, alpha))
-                .stroke_weight(1.0);
+            lines.push((start, end, alpha * start_weight.min(end_weight)));
         }
     }
+    lines
+}
 
-    watermark(&draw);
-    draw.to_frame(app, &frame).unwrap();
+/// The overlay built from the chords leaving a single wedge, rotated into each of the
+/// `num_points` wedges so the result has exact rotational symmetry.
+fn symmetric_overlay_lines(model: &Model) -> Vec<(Point2, Point2, f32)> {
+    let count = model.num_points * 2;
+    let wedge_angle = TAU / model.num_points as f32;
+    // The radius only depends on where a point sits within its wedge.
+    let point = |i: usize| {
+        let angle = (i as f32 / count as f32) * TAU;
+        let local_angle = (i % 2) as f32 / count as f32 * TAU;
+        let r = model.radius * 0.3 * (1.0 + (model.time * 2.0 + local_angle * 2.0).sin() * 0.1);
+        pt2(angle.cos() * r, angle.sin() * r)
+    };
+
+    // Walking at most half way round from each wedge point reaches every chord exactly once,
+    // except diameters which are reached from both ends and so get half the alpha.
+    let mut wedge = Vec::new();
+    for start in 0..2 {
+        for offset in 1..=model.num_points {
+            let alpha = ((model.time + offset as f32 * 0.1).sin() * 0.15 + 0.15).max(0.0);
+            let alpha = if offset == model.num_points {
+                alpha / 2.0
+            } else {
+                alpha
+            };
+            wedge.push((point(start), point(start + offset), alpha));
+        }
+    }
+
+    (0..model.num_points)
+        .flat_map(|k| {
+            let rotation = k as f32 * wedge_angle;
+            wedge
+                .iter()
+                .map(move |(a, b, alpha)| (a.rotate(rotation), b.rotate(rotation), *alpha))
+        })
+        .collect()
 }
 
-fn watermark(draw: &Draw) {
-    draw.text("1.26")
-        .color(LINEN)
-        .font_size(24)
-        .align_text_bottom()
-        .x_y(
-            -(OS_WINDOW_WIDTH as f32) / 2.0 + 40.0,
-            -(OS_WINDOW_HEIGHT as f32) / 2.0 + 110.0,
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stepped(seed: u64, steps: usize) -> Vec<Vec<Point2>> {
+        let mut system = ParticleSystem::new(pt2(100.0, 0.0), hsla(0.5, 0.5, 0.5, 1.0), seed, 3);
+        (0..steps)
+            .map(|_| {
+                system.update(0.0, 0.05, 8);
+                system.particles.iter().map(|p| p.position).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_positions() {
+        let first = stepped(42, 200);
+        assert!(first.iter().any(|positions| !positions.is_empty()));
+        assert_eq!(first, stepped(42, 200));
+    }
+
+    #[test]
+    fn different_seeds_burst_differently() {
+        assert_ne!(stepped(42, 200), stepped(43, 200));
+    }
+
+    #[test]
+    fn saved_state_keeps_the_whole_seed_and_frame() {
+        let path = std::env::temp_dir().join("day26_saved_state_test.txt");
+        let state = SavedState {
+            time: 12.5,
+            pulse_phase: 1.0,
+            color_shift: 0.25,
+            symmetry: 7.0,
+            seed: u64::MAX - 1,
+            frame: 751,
+            burst_symmetry: 6.5,
+            burst_color_shift: 0.2,
+        };
+        state.save(&path).unwrap();
+        let loaded = SavedState::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.seed, u64::MAX - 1);
+        assert_eq!(loaded.frame, 751);
+        assert_eq!(loaded.burst_symmetry, 6.5);
+        assert_eq!(loaded.burst_color_shift, 0.2);
+    }
 }
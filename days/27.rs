@@ -1,14 +1,298 @@
+use clap::{Parser, ValueEnum};
+use nannou::ease;
+use nannou::noise::{NoiseFn, Value};
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::attach_capture;
+use nannou_genuary_2025::common::watermark::{draw_watermark, Corner};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+const GRID_EXTENT: f32 = 600.0; // Width of the area the grid of squares fills
+const SQUARE_FILL: f32 = 100.0 / 120.0; // Fraction of each grid cell the square covers
+const MIC_SMOOTHING: f32 = 0.2; // How quickly the displayed level follows the microphone
+const WINDOW_SIZE: u32 = 800;
+const FRAMES_PER_SECOND: f64 = 60.0; // Frame rate the phase timings are written for
+const NOISE_SIZE_SPEED: f64 = 0.5; // Noise units the size field drifts through per second
+const NOISE_SIZE_DEPTH: f32 = 0.5; // Largest fraction a square grows or shrinks by
+const TILT_FRACTION: f32 = 0.5; // Fraction of each phase spent flipping over under `--tilt`
+const TILT_PERSPECTIVE: f32 = 3.0; // Viewing distance in square sizes (smaller is more dramatic)
+const PALETTE_SAMPLE_SIZE: u32 = 64; // Side the image is shrunk to before its palette is found
+const PALETTE_ITERATIONS: usize = 12; // Rounds of k-means refinement
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Phased grid of squares using nannou")]
+struct Args {
+    /// Scale the squares with the loudness of the default microphone
+    #[arg(long)]
+    mic: bool,
+
+    /// How much a full-scale microphone level enlarges the squares
+    #[arg(long, default_value_t = 4.0)]
+    mic_gain: f32,
+
+    /// Write the first preset as an SVG that animates itself with SMIL, then exit
+    #[arg(long)]
+    svg: Option<PathBuf>,
+
+    /// Run Conway's Game of Life on the grid and only draw the living cells
+    #[arg(long)]
+    life: bool,
+
+    /// Frames between Game of Life generations
+    #[arg(long, default_value_t = 20)]
+    life_tick: u64,
+
+    /// Fraction of cells alive when the Game of Life is seeded
+    #[arg(long, default_value_t = 0.35)]
+    life_density: f32,
+
+    /// Grow and shrink each square by a drifting value-noise field
+    #[arg(long)]
+    noise_size: bool,
+
+    /// Noise units per grid cell for `--noise-size` (smaller gives broader ripples)
+    #[arg(long, default_value_t = 0.3)]
+    noise_scale: f64,
+
+    /// Blend each square's color and size smoothly into its next phase with this easing
+    #[arg(long, value_enum)]
+    ease: Option<Easing>,
+
+    /// Flip each square over like a tile into its next phase, drawn in perspective
+    #[arg(long)]
+    tilt: bool,
+
+    /// Color the phases with the four dominant colors of this image instead of the presets'
+    #[arg(long)]
+    image_palette: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Easing {
+    Linear,
+    Cubic,
+    Elastic,
+    Bounce,
+}
+
+impl Easing {
+    /// Eased progress for `t` from 0 to 1. Elastic overshoots either end.
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::Cubic => ease::cubic::ease_in_out(t, 0.0, 1.0, 1.0),
+            Easing::Elastic => ease::elastic::ease_out(t, 0.0, 1.0, 1.0),
+            Easing::Bounce => ease::bounce::ease_out(t, 0.0, 1.0, 1.0),
+        }
+    }
+}
+
+/// A grid layout, wave pattern, palette and timing that can be swapped in at runtime.
+struct Preset {
+    grid_size: usize,
+    /// Frame offset of the square at (row, col), producing the wave across the grid.
+    pattern: fn(usize, usize, usize) -> u64,
+    palette: [Srgb<u8>; 4],
+    frames_per_phase: u64,
+}
+
+const PRESETS: [Preset; 5] = [
+    Preset {
+        grid_size: 5,
+        pattern: diagonal_wave,
+        palette: [BLUE, GREEN, RED, PURPLE],
+        frames_per_phase: 30,
+    },
+    Preset {
+        grid_size: 7,
+        pattern: row_wave,
+        palette: [NAVY, TEAL, TURQUOISE, AQUAMARINE],
+        frames_per_phase: 20,
+    },
+    Preset {
+        grid_size: 9,
+        pattern: ring_wave,
+        palette: [DARKRED, ORANGERED, ORANGE, GOLD],
+        frames_per_phase: 15,
+    },
+    Preset {
+        grid_size: 6,
+        pattern: checker_wave,
+        palette: [BLACK, DIMGRAY, DARKGRAY, SILVER],
+        frames_per_phase: 40,
+    },
+    Preset {
+        grid_size: 12,
+        pattern: diagonal_wave,
+        palette: [INDIGO, MEDIUMVIOLETRED, HOTPINK, PINK],
+        frames_per_phase: 10,
+    },
+];
+
+fn diagonal_wave(row: usize, col: usize, _grid_size: usize) -> u64 {
+    (row + col) as u64 * 15
+}
+
+fn row_wave(row: usize, _col: usize, _grid_size: usize) -> u64 {
+    row as u64 * 15
+}
+
+fn ring_wave(row: usize, col: usize, grid_size: usize) -> u64 {
+    let center = (grid_size - 1) / 2;
+    (row.abs_diff(center).max(col.abs_diff(center))) as u64 * 15
+}
+
+fn checker_wave(row: usize, col: usize, _grid_size: usize) -> u64 {
+    ((row + col) % 2) as u64 * 60
+}
 
 struct Model {
     squares: Vec<Square>,
     time: u64,
+    preset: usize, // Index into `PRESETS`
+    mic: Option<mic::MicInput>,
+    mic_level: f32, // Smoothed microphone level, 0.0 when there's no microphone
+    life: Option<Life>,
+    noise: Option<Value>,                 // Size field under `--noise-size`
+    image_palette: Option<[Srgb<u8>; 4]>, // Replaces the presets' palettes when set
+    args: Args,
+}
+
+/// Conway's Game of Life on a `size` x `size` torus, indexed like `Model::squares`.
+struct Life {
+    size: usize,
+    alive: Vec<bool>,
+}
+
+impl Life {
+    fn random(size: usize, density: f32) -> Self {
+        Life {
+            size,
+            alive: (0..size * size).map(|_| random_f32() < density).collect(),
+        }
+    }
+
+    fn step(&mut self) {
+        let size = self.size;
+        let next = (0..size * size)
+            .map(|idx| {
+                let (row, col) = (idx / size, idx % size);
+                let mut neighbours = 0;
+                for dr in [size - 1, 0, 1] {
+                    for dc in [size - 1, 0, 1] {
+                        if (dr, dc) != (0, 0)
+                            && self.alive[(row + dr) % size * size + (col + dc) % size]
+                        {
+                            neighbours += 1;
+                        }
+                    }
+                }
+                matches!((self.alive[idx], neighbours), (true, 2) | (_, 3))
+            })
+            .collect();
+        self.alive = next;
+    }
+}
+
+#[cfg(feature = "mic")]
+mod mic {
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    /// The default input device, publishing the RMS level of each buffer it captures.
+    pub struct MicInput {
+        _stream: cpal::Stream,
+        level: Arc<AtomicU32>, // f32 bits, written by the audio thread
+    }
+
+    impl MicInput {
+        pub fn open() -> Result<Self, String> {
+            let device = cpal::default_host()
+                .default_input_device()
+                .ok_or_else(|| "no default input device".to_string())?;
+            let config = device.default_input_config().map_err(|e| e.to_string())?;
+
+            let level = Arc::new(AtomicU32::new(0));
+            let writer = level.clone();
+            let stream = match config.sample_format() {
+                cpal::SampleFormat::F32 => device.build_input_stream(
+                    &config.into(),
+                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                        store_rms(&writer, data.iter().copied())
+                    },
+                    stream_error,
+                    None,
+                ),
+                cpal::SampleFormat::I16 => device.build_input_stream(
+                    &config.into(),
+                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                        store_rms(&writer, data.iter().map(|s| *s as f32 / i16::MAX as f32))
+                    },
+                    stream_error,
+                    None,
+                ),
+                cpal::SampleFormat::U16 => device.build_input_stream(
+                    &config.into(),
+                    move |data: &[u16], _: &cpal::InputCallbackInfo| {
+                        store_rms(
+                            &writer,
+                            data.iter().map(|s| (*s as f32 - 32768.0) / 32768.0),
+                        )
+                    },
+                    stream_error,
+                    None,
+                ),
+                format => return Err(format!("unsupported sample format {:?}", format)),
+            }
+            .map_err(|e| e.to_string())?;
+            stream.play().map_err(|e| e.to_string())?;
+
+            Ok(MicInput {
+                _stream: stream,
+                level,
+            })
+        }
+
+        pub fn level(&self) -> f32 {
+            f32::from_bits(self.level.load(Ordering::Relaxed))
+        }
+    }
+
+    fn store_rms(level: &AtomicU32, samples: impl Iterator<Item = f32>) {
+        let (sum, count) = samples.fold((0.0, 0), |(sum, count), s| (sum + s * s, count + 1));
+        if count > 0 {
+            let rms: f32 = (sum / count as f32).sqrt();
+            level.store(rms.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    fn stream_error(e: cpal::StreamError) {
+        eprintln!("Warning: microphone stream error: {}", e);
+    }
+}
+
+#[cfg(not(feature = "mic"))]
+mod mic {
+    pub struct MicInput;
+
+    impl MicInput {
+        pub fn open() -> Result<Self, String> {
+            Err("built without the `mic` feature".to_string())
+        }
+
+        pub fn level(&self) -> f32 {
+            0.0
+        }
+    }
 }
 
 struct Square {
     position: Point2,
     size: f32,
     phase: u8,
+    phase_progress: f32, // How far through the current phase, from 0 to 1
+    noise_factor: f32,   // Size multiplier from `--noise-size`
 }
 
 impl Square {
@@ -17,68 +301,284 @@ impl Square {
             position: pt2(x, y),
             size,
             phase: 0,
+            phase_progress: 0.0,
+            noise_factor: 1.0,
         }
     }
 
-    fn update(&mut self, time: u64) {
+    fn update(&mut self, time: u64, frames_per_phase: u64) {
         // Systematic phase progression
-        self.phase = ((time / 30) % 4) as u8;
+        self.phase = ((time / frames_per_phase) % 4) as u8;
+        self.phase_progress = (time % frames_per_phase) as f32 / frames_per_phase as f32;
     }
 
-    fn draw(&self, draw: &Draw) {
-        let color = match self.phase {
-            0 => BLUE,
-            1 => GREEN,
-            2 => RED,
-            3 => PURPLE,
-            _ => BLACK,
-        };
+    /// With an `easing` the square is part way to its next phase rather than snapping to it.
+    fn draw(
+        &self,
+        draw: &Draw,
+        palette: &[Srgb<u8>; 4],
+        boost: f32,
+        easing: Option<Easing>,
+        tilt: bool,
+    ) {
+        if tilt {
+            self.draw_tilted(draw, palette, boost, easing);
+            return;
+        }
 
-        // Size oscillation based on phase
-        let scale = match self.phase {
-            0 => 1.0,
-            1 => 0.8,
-            2 => 0.6,
-            3 => 0.4,
-            _ => 1.0,
+        let color: Srgb = palette
+            .get(self.phase as usize)
+            .copied()
+            .unwrap_or(BLACK)
+            .into_format();
+        let mut scale = phase_scale(self.phase);
+
+        let color = match easing {
+            Some(easing) => {
+                let next = (self.phase + 1) % 4;
+                let t = easing.apply(self.phase_progress);
+                let next_color: Srgb = palette[next as usize].into_format();
+                scale += (phase_scale(next) - scale) * t;
+                let mix = |a: f32, b: f32| (a + (b - a) * t).clamp(0.0, 1.0);
+                srgb(
+                    mix(color.red, next_color.red),
+                    mix(color.green, next_color.green),
+                    mix(color.blue, next_color.blue),
+                )
+            }
+            None => color,
         };
 
+        let scale = scale * boost * self.noise_factor;
         draw.rect()
             .xy(self.position)
             .w_h(self.size * scale, self.size * scale)
             .color(color);
     }
+
+    /// Over the last `TILT_FRACTION` of its phase the square turns about its horizontal axis,
+    /// its far edge foreshortened, and lands showing the next phase on its back. An `easing`
+    /// shapes the turn.
+    fn draw_tilted(
+        &self,
+        draw: &Draw,
+        palette: &[Srgb<u8>; 4],
+        boost: f32,
+        easing: Option<Easing>,
+    ) {
+        let flip = ((self.phase_progress - (1.0 - TILT_FRACTION)) / TILT_FRACTION).max(0.0);
+        let angle = easing.map_or(flip, |easing| easing.apply(flip)) * PI;
+        let phase = if angle.cos() < 0.0 {
+            (self.phase + 1) % 4
+        } else {
+            self.phase
+        };
+
+        let half = self.size * phase_scale(phase) * boost * self.noise_factor / 2.0;
+        let distance = self.size * TILT_PERSPECTIVE;
+        let corners = [(-half, -half), (half, -half), (half, half), (-half, half)].map(|(x, y)| {
+            // Turn the corner about the x axis, then project it back onto the screen.
+            let depth = y * angle.sin();
+            let perspective = distance / (distance + depth).max(1.0);
+            self.position + vec2(x, y * angle.cos()) * perspective
+        });
+        draw.polygon()
+            .points(corners)
+            .color(palette[phase as usize]);
+    }
 }
 
-fn model(app: &App) -> Model {
-    app.new_window().size(800, 800).view(view).build().unwrap();
+/// Size oscillation based on phase.
+fn phase_scale(phase: u8) -> f32 {
+    match phase {
+        0 => 1.0,
+        1 => 0.8,
+        2 => 0.6,
+        3 => 0.4,
+        _ => 1.0,
+    }
+}
 
-    // Create a 5x5 grid of squares
-    let mut squares = Vec::new();
-    let square_size = 100.0;
-    let spacing = 120.0;
-    let offset = -240.0; // Center the grid
+/// The squares of `preset`'s grid, in the order `Preset::pattern` is indexed by.
+fn build_squares(preset: &Preset) -> Vec<Square> {
+    let spacing = GRID_EXTENT / preset.grid_size as f32;
+    let square_size = spacing * SQUARE_FILL;
+    let offset = -((preset.grid_size - 1) as f32) * spacing / 2.0; // Center the grid
 
-    for i in 0..5 {
-        for j in 0..5 {
+    let mut squares = Vec::with_capacity(preset.grid_size * preset.grid_size);
+    for i in 0..preset.grid_size {
+        for j in 0..preset.grid_size {
             let x = offset + (i as f32 * spacing);
             let y = offset + (j as f32 * spacing);
             squares.push(Square::new(x, y, square_size));
         }
     }
+    squares
+}
+
+/// Switches the model to the preset at index `n` of `PRESETS`, rebuilding the grid.
+fn apply_preset(model: &mut Model, n: usize) {
+    let Some(preset) = PRESETS.get(n) else {
+        return;
+    };
+
+    model.squares = build_squares(preset);
+    model.preset = n;
+    if model.args.life {
+        model.life = Some(Life::random(preset.grid_size, model.args.life_density));
+    }
+}
+
+fn model(app: &App) -> Model {
+    let args = Args::parse();
+    attach_capture(
+        app.new_window()
+            .size(WINDOW_SIZE, WINDOW_SIZE)
+            .view(view)
+            .key_pressed(key_pressed),
+    )
+    .build()
+    .unwrap();
+
+    let mic = if args.mic {
+        match mic::MicInput::open() {
+            Ok(mic) => Some(mic),
+            Err(e) => {
+                eprintln!(
+                    "Warning: microphone unavailable ({}), continuing without it",
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let image_palette =
+        args.image_palette
+            .as_deref()
+            .and_then(|path| match palette_from_image(path) {
+                Ok(palette) => Some(palette),
+                Err(e) => {
+                    eprintln!(
+                        "Warning: couldn't read a palette from {} ({}), using the presets' colors",
+                        path.display(),
+                        e
+                    );
+                    None
+                }
+            });
+
+    let mut model = Model {
+        squares: Vec::new(),
+        time: 0,
+        preset: 0,
+        mic,
+        mic_level: 0.0,
+        life: None,
+        noise: args.noise_size.then(Value::new),
+        image_palette,
+        args,
+    };
+    apply_preset(&mut model, 0);
+    model
+}
+
+/// The four dominant colors of the image at `path` by k-means, darkest first so the phases
+/// step from dark to light.
+fn palette_from_image(path: &Path) -> Result<[Srgb<u8>; 4], String> {
+    let image = nannou::image::open(path).map_err(|e| e.to_string())?;
+    let pixels: Vec<[f32; 3]> = image
+        .thumbnail(PALETTE_SAMPLE_SIZE, PALETTE_SAMPLE_SIZE)
+        .to_rgb8()
+        .pixels()
+        .map(|p| p.0.map(|c| c as f32))
+        .collect();
+    if pixels.is_empty() {
+        return Err("the image is empty".to_string());
+    }
+
+    let luma = |c: &[f32; 3]| 0.299 * c[0] + 0.587 * c[1] + 0.114 * c[2];
+    let distance = |a: &[f32; 3], b: &[f32; 3]| (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f32>();
+
+    // Seed the clusters at evenly spaced brightnesses so the result doesn't depend on chance.
+    let mut by_luma = pixels.clone();
+    by_luma.sort_by(|a, b| luma(a).total_cmp(&luma(b)));
+    let mut centers: [[f32; 3]; 4] =
+        std::array::from_fn(|k| by_luma[(2 * k + 1) * by_luma.len() / 8]);
+
+    for _ in 0..PALETTE_ITERATIONS {
+        let mut sums = [[0.0; 3]; 4];
+        let mut counts = [0usize; 4];
+        for pixel in &pixels {
+            let nearest = (0..4)
+                .min_by(|&a, &b| {
+                    distance(pixel, &centers[a]).total_cmp(&distance(pixel, &centers[b]))
+                })
+                .unwrap_or(0);
+            for (sum, value) in sums[nearest].iter_mut().zip(pixel) {
+                *sum += value;
+            }
+            counts[nearest] += 1;
+        }
+        // A cluster nothing is nearest to keeps its old center.
+        for ((center, sum), count) in centers.iter_mut().zip(sums).zip(counts) {
+            if count > 0 {
+                *center = sum.map(|sum| sum / count as f32);
+            }
+        }
+    }
 
-    Model { squares, time: 0 }
+    centers.sort_by(|a, b| luma(a).total_cmp(&luma(b)));
+    Ok(centers.map(|c| Srgb::new(c[0].round() as u8, c[1].round() as u8, c[2].round() as u8)))
+}
+
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    let preset = match key {
+        Key::Key1 => 0,
+        Key::Key2 => 1,
+        Key::Key3 => 2,
+        Key::Key4 => 3,
+        Key::Key5 => 4,
+        _ => return,
+    };
+    apply_preset(model, preset);
 }
 
 fn update(_app: &App, model: &mut Model, _update: Update) {
     model.time += 1;
 
+    if let Some(mic) = &model.mic {
+        model.mic_level += (mic.level() - model.mic_level) * MIC_SMOOTHING;
+    }
+
     // Update each square with a different timing offset based on position
+    let preset = &PRESETS[model.preset];
     for (idx, square) in model.squares.iter_mut().enumerate() {
-        let row = idx / 5;
-        let col = idx % 5;
-        let offset = (row + col) as u64 * 15; // Diagonal wave pattern
-        square.update(model.time + offset);
+        let row = idx / preset.grid_size;
+        let col = idx % preset.grid_size;
+        let offset = (preset.pattern)(row, col, preset.grid_size);
+        square.update(model.time + offset, preset.frames_per_phase);
+
+        if let Some(noise) = &model.noise {
+            let value = noise.get([
+                row as f64 * model.args.noise_scale,
+                col as f64 * model.args.noise_scale,
+                model.time as f64 / FRAMES_PER_SECOND * NOISE_SIZE_SPEED,
+            ]);
+            square.noise_factor = 1.0 + value as f32 * NOISE_SIZE_DEPTH;
+        }
+    }
+
+    if let Some(life) = &mut model.life {
+        if model.time.is_multiple_of(model.args.life_tick.max(1)) {
+            life.step();
+            // Start over rather than leave an empty grid.
+            if !life.alive.contains(&true) {
+                *life = Life::random(life.size, model.args.life_density);
+            }
+        }
     }
 }
 
@@ -86,22 +586,99 @@ fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
     draw.background().color(LINEN);
 
-    for square in &model.squares {
-        square.draw(&draw);
+    let palette = model
+        .image_palette
+        .as_ref()
+        .unwrap_or(&PRESETS[model.preset].palette);
+    let boost = 1.0 + model.mic_level * model.args.mic_gain;
+    for (idx, square) in model.squares.iter().enumerate() {
+        if model.life.as_ref().is_some_and(|life| !life.alive[idx]) {
+            continue;
+        }
+        square.draw(&draw, palette, boost, model.args.ease, model.args.tilt);
     }
 
-    watermark(&draw);
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        "1.27",
+        Corner::BottomLeft,
+        None,
+        LINEN.into_format(),
+    );
     draw.to_frame(app, &frame).unwrap();
 }
 
-fn watermark(draw: &Draw) {
-    draw.text("1.27")
-        .color(rgba(0.0, 0.0, 0.0, 0.5))
-        .font_size(24)
-        .align_text_bottom()
-        .x_y(-(800.0 as f32) / 2.0 + 40.0, -(800.0 as f32) / 2.0 + 110.0);
-}
-
 fn main() {
+    if let Some(path) = Args::parse().svg {
+        match std::fs::write(&path, svg_animation(&PRESETS[0])) {
+            Ok(()) => println!("Wrote {}", path.display()),
+            Err(e) => eprintln!("Failed to write {}: {}", path.display(), e),
+        }
+        return;
+    }
     nannou::app(model).update(update).run();
 }
+
+/// Renders `preset` as an SVG whose squares step through their phases with discrete SMIL
+/// animations. Each square's cycle starts part way through by its pattern offset, so the
+/// wave lines up with what the sketch shows.
+fn svg_animation(preset: &Preset) -> String {
+    let half = WINDOW_SIZE as f32 / 2.0;
+    let cycle_seconds = 4.0 * preset.frames_per_phase as f64 / FRAMES_PER_SECOND;
+    let hex = |c: Srgb<u8>| format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue);
+    let joined = |values: Vec<String>| values.join(";");
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="{min} {min} {size} {size}">"#,
+        size = WINDOW_SIZE,
+        min = -half,
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{min}" y="{min}" width="{size}" height="{size}" fill="{fill}"/>"#,
+        min = -half,
+        size = WINDOW_SIZE,
+        fill = hex(LINEN),
+    );
+
+    let fills = joined(preset.palette.iter().map(|c| hex(*c)).collect());
+    let scales = joined((0..4).map(|phase| phase_scale(phase).to_string()).collect());
+    for (idx, square) in build_squares(preset).iter().enumerate() {
+        let row = idx / preset.grid_size;
+        let col = idx % preset.grid_size;
+        let offset = (preset.pattern)(row, col, preset.grid_size);
+        let begin = -(offset as f64) / FRAMES_PER_SECOND;
+
+        // SVG's y axis points down, so flip the sketch's coordinates.
+        let _ = writeln!(
+            svg,
+            r#"<g transform="translate({x} {y})"><rect x="{corner}" y="{corner}" width="{size}" height="{size}" fill="{fill}">"#,
+            x = square.position.x,
+            y = -square.position.y,
+            corner = -square.size / 2.0,
+            size = square.size,
+            fill = hex(preset.palette[0]),
+        );
+        let _ = writeln!(
+            svg,
+            r#"<animate attributeName="fill" values="{fills}" calcMode="discrete" dur="{cycle_seconds}s" begin="{begin}s" repeatCount="indefinite"/>"#,
+        );
+        let _ = writeln!(
+            svg,
+            r#"<animateTransform attributeName="transform" type="scale" values="{scales}" calcMode="discrete" dur="{cycle_seconds}s" begin="{begin}s" repeatCount="indefinite"/>"#,
+        );
+        svg.push_str("</rect></g>\n");
+    }
+
+    let _ = writeln!(
+        svg,
+        r#"<text x="{x}" y="{y}" font-size="24" fill="black" fill-opacity="0.5">1.27</text>"#,
+        x = -half + 40.0,
+        y = half - 110.0,
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
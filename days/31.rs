@@ -1,12 +1,51 @@
+use clap::{Parser, ValueEnum};
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::attach_capture;
+use nannou_genuary_2025::common::lerp::lerp;
+use nannou_genuary_2025::common::watermark::{corner_point, draw_watermark, Corner};
 use rand::Rng;
 
 const PIXEL_GRID_WIDTH: usize = 200;
 const PIXEL_GRID_HEIGHT: usize = 200;
 const DISPLAY_WINDOW_WIDTH: u32 = 800;
 const DISPLAY_WINDOW_HEIGHT: u32 = 800;
-const STEPS_PER_RANDOMIZATION: u32 = 50;
+const STEPS_PER_RANDOMIZATION: u32 = 50; // Average swaps per step, whatever the curve
 const NUM_RANDOMIZATIONS: usize = 2000;
+const PREVIEW_STRIDE: usize = 4; // Grid pixels per side sampled into each pixel of the preview
+const PREVIEW_PIXEL_SIZE: f32 = 3.0;
+const PREVIEW_MARGIN: f32 = 20.0; // Gap between the preview and the window's corner
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Scrambled gradient using nannou")]
+struct Args {
+    /// How the number of swaps per step changes over the scramble
+    #[arg(long, value_enum, default_value_t = ScrambleCurve::Linear)]
+    scramble_curve: ScrambleCurve,
+
+    /// Show the unscrambled gradient as a small inset in the top-right corner
+    #[arg(long)]
+    preview: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ScrambleCurve {
+    Linear,
+    EaseIn,
+    EaseOut,
+}
+
+impl ScrambleCurve {
+    /// Swaps to make at fraction `t` of the way through the scramble. Every curve makes
+    /// the same number of swaps overall, just sooner or later.
+    fn swaps(self, t: f32) -> u32 {
+        let weight = match self {
+            ScrambleCurve::Linear => 1.0,
+            ScrambleCurve::EaseIn => 3.0 * t * t,
+            ScrambleCurve::EaseOut => 3.0 * (1.0 - t) * (1.0 - t),
+        };
+        (STEPS_PER_RANDOMIZATION as f32 * weight).round() as u32
+    }
+}
 
 struct Model {
     target: Vec<Rgb8>,
@@ -14,6 +53,8 @@ struct Model {
     indices: Vec<usize>,
     randomization_step: usize,
     finished: bool,
+    scramble_curve: ScrambleCurve,
+    preview: bool,
 }
 
 fn main() {
@@ -21,12 +62,14 @@ fn main() {
 }
 
 fn model(app: &App) -> Model {
-    let _window = app
-        .new_window()
-        .size(DISPLAY_WINDOW_WIDTH as u32, DISPLAY_WINDOW_HEIGHT as u32)
-        .view(view)
-        .build()
-        .unwrap();
+    let args = Args::parse();
+    let _window = attach_capture(
+        app.new_window()
+            .size(DISPLAY_WINDOW_WIDTH as u32, DISPLAY_WINDOW_HEIGHT as u32)
+            .view(view),
+    )
+    .build()
+    .unwrap();
 
     // Generate target gradient
     let mut target = vec![Rgb8::new(0, 0, 0); PIXEL_GRID_WIDTH * PIXEL_GRID_HEIGHT];
@@ -52,13 +95,11 @@ fn model(app: &App) -> Model {
         indices,
         randomization_step: 0,
         finished: false,
+        scramble_curve: args.scramble_curve,
+        preview: args.preview,
     }
 }
 
-fn lerp(start: f32, end: f32, t: f32) -> f32 {
-    start + (end - start) * t
-}
-
 fn update(_app: &App, model: &mut Model, _update: Update) {
     if model.finished {
         return;
@@ -68,7 +109,8 @@ fn update(_app: &App, model: &mut Model, _update: Update) {
 
     // Perform random swaps
     if model.randomization_step > 2 {
-        for _ in 0..STEPS_PER_RANDOMIZATION {
+        let t = model.randomization_step as f32 / NUM_RANDOMIZATIONS as f32;
+        for _ in 0..model.scramble_curve.swaps(t) {
             let i = rng.gen_range(0..model.indices.len());
             let j = rng.gen_range(0..model.indices.len());
             model.indices.swap(i, j);
@@ -113,17 +155,42 @@ fn view(app: &App, model: &Model, frame: Frame) {
         }
     }
 
-    watermark(&draw);
+    if model.preview {
+        draw_preview(model, &draw, app.window_rect());
+    }
+
+    // The scramble behind it can be any color, so white rather than a contrast pick.
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        "1.31",
+        Corner::BottomLeft,
+        Some(rgba(1.0, 1.0, 1.0, 1.0)),
+        BLACK.into_format(),
+    );
     draw.to_frame(app, &frame).unwrap();
 }
 
-fn watermark(draw: &Draw) {
-    draw.text("1.31")
-        .color(WHITE)
-        .font_size(24)
-        .align_text_bottom()
-        .x_y(
-            -(DISPLAY_WINDOW_WIDTH as f32) / 2.0 + 40.0,
-            -(DISPLAY_WINDOW_HEIGHT as f32) / 2.0 + 110.0,
-        );
+/// The target at a fraction of its size in the top-right corner, framed so it stands out
+/// against the scramble behind it.
+fn draw_preview(model: &Model, draw: &Draw, rect: Rect) {
+    let cols = PIXEL_GRID_WIDTH / PREVIEW_STRIDE;
+    let rows = PIXEL_GRID_HEIGHT / PREVIEW_STRIDE;
+    let size = vec2(cols as f32, rows as f32) * PREVIEW_PIXEL_SIZE;
+    let corner = corner_point(rect, Corner::TopRight, size + PREVIEW_MARGIN);
+
+    draw.rect()
+        .xy(corner + size / 2.0)
+        .wh(size + 4.0)
+        .color(WHITE);
+    for row in 0..rows {
+        for col in 0..cols {
+            let color =
+                model.target[row * PREVIEW_STRIDE * PIXEL_GRID_WIDTH + col * PREVIEW_STRIDE];
+            draw.rect()
+                .xy(corner + (vec2(col as f32, row as f32) + 0.5) * PREVIEW_PIXEL_SIZE)
+                .w_h(PREVIEW_PIXEL_SIZE, PREVIEW_PIXEL_SIZE)
+                .color(color);
+        }
+    }
 }
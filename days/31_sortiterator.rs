@@ -1,11 +1,114 @@
+use clap::{Parser, ValueEnum};
+use nannou::image::imageops::FilterType;
+use nannou::image::{Rgb, RgbImage};
 use nannou::prelude::*;
+use nannou_genuary_2025::common::capture::attach_capture;
+use nannou_genuary_2025::common::lerp::lerp;
+use nannou_genuary_2025::common::watermark::{corner_point, draw_watermark, Corner};
 use rand::Rng;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 
 const PIXEL_GRID_WIDTH: usize = 200;
 const PIXEL_GRID_HEIGHT: usize = 200;
 const DISPLAY_WINDOW_WIDTH: u32 = 800;
 const DISPLAY_WINDOW_HEIGHT: u32 = 800;
 const NUM_SORTS_PER_FRAME: usize = 5000000;
+const NUM_QUICK_SORTS_PER_FRAME: usize = 2000;
+const NUM_BANDED_SORTS_PER_FRAME: usize = 20000;
+const FINALE_MIN_SPEED: f32 = 0.01; // Slowest the finale gets, as a fraction of the full speed
+const MAX_SNAPSHOTS: usize = 300; // Beyond this every other snapshot is dropped and the interval doubled
+const HISTOGRAM_BINS: usize = 32;
+const HISTOGRAM_ROWS: usize = 50; // Rows at the bottom of the grid the histogram covers
+const HISTOGRAM_WIDTH: f32 = 192.0;
+const HISTOGRAM_HEIGHT: f32 = 96.0;
+const ENTROPY_BAR_WIDTH: f32 = 200.0;
+const ENTROPY_BAR_HEIGHT: f32 = 10.0;
+const MONTAGE_GAP: u32 = 4; // Pixels between tiles of the montage
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Pixel sorting visualization using nannou")]
+struct Args {
+    /// Sorting algorithm to visualize
+    #[arg(long, value_enum, default_value_t = Algorithm::Bubble)]
+    algo: Algorithm,
+
+    /// Sort steps per frame (defaults to a speed suited to the algorithm)
+    #[arg(long)]
+    steps_per_frame: Option<usize>,
+
+    /// Fraction of neighbouring pixels still out of order below which the sort slows down
+    /// so the final assembly is visible (0 disables)
+    #[arg(long, default_value_t = 0.02)]
+    finale_slowdown: f32,
+
+    /// Overlay a brightness histogram of the bottom rows alongside the target's
+    #[arg(long)]
+    histogram: bool,
+
+    /// Sort by brightness only within runs of bright pixels along each row or column,
+    /// like classic glitch-art pixel sorting (every band is bubble sorted, ignoring `--algo`)
+    #[arg(long, value_enum)]
+    bands: Option<BandDirection>,
+
+    /// Brightness (0-255) a pixel needs to be part of a band; darker pixels split the runs
+    #[arg(long, default_value_t = 80.0)]
+    band_threshold: f32,
+
+    /// Frames between the snapshots kept for scrubbing with Left/Right while paused (0 disables)
+    #[arg(long, default_value_t = 10)]
+    snapshot_interval: u64,
+
+    /// Sort this image, resized to the grid, instead of the built-in gradient
+    #[arg(long)]
+    image: Option<PathBuf>,
+
+    /// Dither the target while reducing it to the grid
+    #[arg(long, value_enum, default_value_t = Dither::None)]
+    dither: Dither,
+
+    /// Levels per color channel the target is reduced to when dithering
+    #[arg(long, default_value_t = 6)]
+    dither_levels: u8,
+
+    /// Lay the sorted order out as a serpentine, reversing direction on every other row
+    #[arg(long)]
+    snake: bool,
+
+    /// Show how far the pixels still are from where they end up, as a number and a bar
+    #[arg(long)]
+    show_entropy: bool,
+
+    /// When the sort finishes, save evenly spaced snapshots of it tiled into this PNG
+    #[arg(long)]
+    montage: Option<PathBuf>,
+
+    /// Number of snapshots in the `--montage`
+    #[arg(long, default_value_t = 16)]
+    montage_count: usize,
+
+    /// Tiles per row of the `--montage`
+    #[arg(long, default_value_t = 4)]
+    montage_cols: usize,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Algorithm {
+    Bubble,
+    Quick,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Dither {
+    None,
+    FloydSteinberg,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum BandDirection {
+    Rows,
+    Cols,
+}
 
 #[derive(Copy, Clone, Debug)]
 struct Pixel {
@@ -35,16 +138,133 @@ impl Eq for Pixel {}
 
 struct Model {
     finished: bool,
-    sorter: Box<BubbleSort<Pixel>>,
+    sorter: Box<dyn StepSort<Pixel>>,
+    steps_per_frame: usize,
+    finale_slowdown: f32,
+    histogram: bool,
+    target_histogram: [u32; HISTOGRAM_BINS],
+    frame: u64,
+    snapshot_interval: u64,       // Doubles each time `history` is thinned
+    history: VecDeque<Vec<Rgb8>>, // Grid colors every `snapshot_interval` frames, oldest first
+    paused: bool,
+    scrub: Option<usize>, // Index into `history` being shown instead of the live grid
+    snake: bool,
+    show_entropy: bool,
+    target_position: Vec<usize>, // Final position of each pixel, by `Pixel::idx`
+    initial_displacement: u64,
+    montage: Option<Montage>,
+}
+
+/// Where `--montage` goes and how it's laid out.
+struct Montage {
+    path: PathBuf,
+    count: usize,
+    cols: usize,
 }
 
 impl Model {
-    fn new(current: Vec<Pixel>) -> Self {
-        Model {
+    fn new(mut current: Vec<Pixel>, args: &Args) -> Self {
+        let bands = args.bands.map(|direction| {
+            if !args.snake {
+                return brightness_bands(&current, direction, args.band_threshold);
+            }
+            // Bands are found on the grid but sorted in serpentine order.
+            let grid: Vec<Pixel> = (0..current.len()).map(|i| current[snake_cell(i)]).collect();
+            let mut bands = brightness_bands(&grid, direction, args.band_threshold);
+            for idx in bands.iter_mut().flatten() {
+                *idx = snake_cell(*idx);
+            }
+            bands
+        });
+        if bands.is_some() {
+            rank_by_brightness(&mut current);
+        }
+
+        // Histogram of the bottom rows once everything has been sorted
+        let mut sorted = current.clone();
+        match &bands {
+            Some(bands) => {
+                for band in bands {
+                    let mut pixels: Vec<Pixel> = band.iter().map(|&i| sorted[i]).collect();
+                    pixels.sort();
+                    for (&i, pixel) in band.iter().zip(pixels) {
+                        sorted[i] = pixel;
+                    }
+                }
+            }
+            None => sorted.sort(),
+        }
+        let target_histogram = brightness_histogram(
+            sorted[..HISTOGRAM_ROWS * PIXEL_GRID_WIDTH]
+                .iter()
+                .map(|p| p.color),
+        );
+        let mut target_position = vec![0; sorted.len()];
+        for (position, pixel) in sorted.iter().enumerate() {
+            target_position[pixel.idx] = position;
+        }
+        let initial_displacement = displacement(&current, &target_position);
+
+        let (sorter, default_steps): (Box<dyn StepSort<Pixel>>, usize) = match (bands, args.algo) {
+            (Some(bands), _) => (
+                Box::new(BandedSort::new(current.into_iter(), bands)),
+                NUM_BANDED_SORTS_PER_FRAME,
+            ),
+            (None, Algorithm::Bubble) => (
+                Box::new(BubbleSort::new(current.into_iter())),
+                NUM_SORTS_PER_FRAME,
+            ),
+            (None, Algorithm::Quick) => (
+                Box::new(QuickSort::new(current.into_iter())),
+                NUM_QUICK_SORTS_PER_FRAME,
+            ),
+        };
+        let mut model = Model {
             finished: false,
-            sorter: Box::new(BubbleSort::new(current.into_iter())),
+            sorter,
+            steps_per_frame: args.steps_per_frame.unwrap_or(default_steps),
+            finale_slowdown: args.finale_slowdown,
+            histogram: args.histogram,
+            target_histogram,
+            frame: 0,
+            snapshot_interval: args.snapshot_interval,
+            history: VecDeque::new(),
+            paused: false,
+            scrub: None,
+            snake: args.snake,
+            show_entropy: args.show_entropy,
+            target_position,
+            initial_displacement,
+            montage: args.montage.clone().map(|path| Montage {
+                path,
+                count: args.montage_count.max(1),
+                cols: args.montage_cols.max(1),
+            }),
+        };
+        // The montage and scrubbing both start from the scrambled grid.
+        if model.snapshot_interval > 0 {
+            model.take_snapshot();
         }
+        model
     }
+
+    fn take_snapshot(&mut self) {
+        let colors = self.sorter.items().iter().map(|p| p.color).collect();
+        self.history.push_back(colors);
+        if self.history.len() > MAX_SNAPSHOTS {
+            thin_history(&mut self.history);
+            self.snapshot_interval *= 2;
+        }
+    }
+}
+
+/// Drops every other snapshot, keeping the first so the scrambled start is never lost.
+fn thin_history<T>(history: &mut VecDeque<T>) {
+    let mut index = 0;
+    history.retain(|_| {
+        index += 1;
+        index % 2 == 1
+    });
 }
 
 fn main() {
@@ -52,26 +272,33 @@ fn main() {
 }
 
 fn model(app: &App) -> Model {
-    let _window = app
-        .new_window()
-        .size(DISPLAY_WINDOW_WIDTH as u32, DISPLAY_WINDOW_HEIGHT as u32)
-        .view(view)
-        .build()
-        .unwrap();
-
-    // Generate target gradient
-    let mut colors = vec![Rgb8::new(0, 0, 0); PIXEL_GRID_WIDTH * PIXEL_GRID_HEIGHT];
-    for y in 0..PIXEL_GRID_HEIGHT {
-        for x in 0..PIXEL_GRID_WIDTH {
-            let r = lerp(0.0, 255.0, y as f32 / PIXEL_GRID_HEIGHT as f32) as u8;
-            let g = lerp(
-                0.0,
-                255.0,
-                (x + y) as f32 / (PIXEL_GRID_WIDTH + PIXEL_GRID_HEIGHT) as f32,
-            ) as u8;
-            let b = lerp(255.0, 0.0, y as f32 / PIXEL_GRID_HEIGHT as f32) as u8;
-            colors[y * PIXEL_GRID_WIDTH + x] = Rgb8::new(r, g, b);
-        }
+    let args = Args::parse();
+    let _window = attach_capture(
+        app.new_window()
+            .size(DISPLAY_WINDOW_WIDTH, DISPLAY_WINDOW_HEIGHT)
+            .view(view)
+            .key_pressed(key_pressed),
+    )
+    .build()
+    .unwrap();
+
+    let mut colors = match &args.image {
+        Some(path) => load_image(path).unwrap_or_else(|e| {
+            eprintln!(
+                "Warning: couldn't load image {} ({}), sorting the gradient instead",
+                path.display(),
+                e
+            );
+            gradient()
+        }),
+        None => gradient(),
+    };
+    if let Dither::FloydSteinberg = args.dither {
+        floyd_steinberg(&mut colors, args.dither_levels);
+    }
+    if args.snake {
+        // Put the target in the order the serpentine visits the grid.
+        colors = (0..colors.len()).map(|i| colors[snake_cell(i)]).collect();
     }
 
     // Create target indices (sorted order)
@@ -91,11 +318,186 @@ fn model(app: &App) -> Model {
             .zip(current_indices.iter())
             .map(|(color, &idx)| Pixel { color: *color, idx })
             .collect(),
+        &args,
     )
 }
 
-fn lerp(start: f32, end: f32, t: f32) -> f32 {
-    start + (end - start) * t
+/// The default target: red to blue top to bottom, with green rising along the diagonal.
+fn gradient() -> Vec<Rgb8> {
+    let mut colors = vec![Rgb8::new(0, 0, 0); PIXEL_GRID_WIDTH * PIXEL_GRID_HEIGHT];
+    for y in 0..PIXEL_GRID_HEIGHT {
+        for x in 0..PIXEL_GRID_WIDTH {
+            let r = lerp(0.0, 255.0, y as f32 / PIXEL_GRID_HEIGHT as f32) as u8;
+            let g = lerp(
+                0.0,
+                255.0,
+                (x + y) as f32 / (PIXEL_GRID_WIDTH + PIXEL_GRID_HEIGHT) as f32,
+            ) as u8;
+            let b = lerp(255.0, 0.0, y as f32 / PIXEL_GRID_HEIGHT as f32) as u8;
+            colors[y * PIXEL_GRID_WIDTH + x] = Rgb8::new(r, g, b);
+        }
+    }
+    colors
+}
+
+/// Loads the image at `path` squashed to the grid, in row-major order from the bottom row
+/// up to match the grid `pixel_position` lays out.
+fn load_image(path: &Path) -> Result<Vec<Rgb8>, String> {
+    let image = nannou::image::open(path)
+        .map_err(|e| e.to_string())?
+        .flipv()
+        .resize_exact(
+            PIXEL_GRID_WIDTH as u32,
+            PIXEL_GRID_HEIGHT as u32,
+            FilterType::Triangle,
+        )
+        .to_rgb8();
+    Ok(image
+        .pixels()
+        .map(|p| Rgb8::new(p[0], p[1], p[2]))
+        .collect())
+}
+
+/// Reduces each channel of the row-major grid to `levels` evenly spaced values, spreading
+/// the rounding error onto the neighbours still to come so the average color survives.
+fn floyd_steinberg(colors: &mut [Rgb8], levels: u8) {
+    let step = 255.0 / (levels.max(2) - 1) as f32;
+    let mut buffer: Vec<[f32; 3]> = colors
+        .iter()
+        .map(|c| [c.red as f32, c.green as f32, c.blue as f32])
+        .collect();
+
+    for y in 0..PIXEL_GRID_HEIGHT {
+        for x in 0..PIXEL_GRID_WIDTH {
+            let i = y * PIXEL_GRID_WIDTH + x;
+            let old = buffer[i];
+            let new = old.map(|v| ((v / step).round() * step).clamp(0.0, 255.0));
+            buffer[i] = new;
+
+            for (dx, dy, weight) in [
+                (1, 0, 7.0 / 16.0),
+                (-1, 1, 3.0 / 16.0),
+                (0, 1, 5.0 / 16.0),
+                (1, 1, 1.0 / 16.0),
+            ] {
+                let nx = x as isize + dx;
+                let ny = y + dy;
+                if nx < 0 || nx >= PIXEL_GRID_WIDTH as isize || ny >= PIXEL_GRID_HEIGHT {
+                    continue;
+                }
+                let neighbour = &mut buffer[ny * PIXEL_GRID_WIDTH + nx as usize];
+                for ((channel, old), new) in neighbour.iter_mut().zip(old).zip(new) {
+                    *channel += (old - new) * weight;
+                }
+            }
+        }
+    }
+
+    for (color, [r, g, b]) in colors.iter_mut().zip(buffer) {
+        *color = Rgb8::new(r as u8, g as u8, b as u8);
+    }
+}
+
+/// Perceived brightness of `color`, from 0 to 255.
+fn luma(color: Rgb8) -> f32 {
+    0.299 * color.red as f32 + 0.587 * color.green as f32 + 0.114 * color.blue as f32
+}
+
+/// Counts of `colors` by perceived brightness, darkest bin first.
+fn brightness_histogram(colors: impl Iterator<Item = Rgb8>) -> [u32; HISTOGRAM_BINS] {
+    let mut bins = [0; HISTOGRAM_BINS];
+    for color in colors {
+        let bin = (luma(color) / 256.0 * HISTOGRAM_BINS as f32) as usize;
+        bins[bin.min(HISTOGRAM_BINS - 1)] += 1;
+    }
+    bins
+}
+
+/// Grid positions of each run of at least two pixels at or above `threshold` brightness,
+/// walking along rows or columns.
+fn brightness_bands(pixels: &[Pixel], direction: BandDirection, threshold: f32) -> Vec<Vec<usize>> {
+    let (lines, length) = match direction {
+        BandDirection::Rows => (PIXEL_GRID_HEIGHT, PIXEL_GRID_WIDTH),
+        BandDirection::Cols => (PIXEL_GRID_WIDTH, PIXEL_GRID_HEIGHT),
+    };
+
+    let mut bands = Vec::new();
+    for line in 0..lines {
+        let mut run = Vec::new();
+        for along in 0..length {
+            let idx = match direction {
+                BandDirection::Rows => line * PIXEL_GRID_WIDTH + along,
+                BandDirection::Cols => along * PIXEL_GRID_WIDTH + line,
+            };
+            if luma(pixels[idx].color) >= threshold {
+                run.push(idx);
+            } else if !run.is_empty() {
+                bands.push(std::mem::take(&mut run));
+            }
+        }
+        bands.push(run);
+    }
+    bands.retain(|band| band.len() > 1);
+    bands
+}
+
+/// Makes the pixels order by brightness rather than by their place in the gradient.
+fn rank_by_brightness(pixels: &mut [Pixel]) {
+    let mut order: Vec<usize> = (0..pixels.len()).collect();
+    order.sort_by(|&a, &b| luma(pixels[a].color).total_cmp(&luma(pixels[b].color)));
+    for (rank, i) in order.into_iter().enumerate() {
+        pixels[i].idx = rank;
+    }
+}
+
+/// Total distance, in places along the sort order, of every pixel from its final position.
+fn displacement(pixels: &[Pixel], target_position: &[usize]) -> u64 {
+    pixels
+        .iter()
+        .enumerate()
+        .map(|(position, pixel)| position.abs_diff(target_position[pixel.idx]) as u64)
+        .sum()
+}
+
+/// Positions of interest in a sorter's current state, for highlighting.
+#[derive(Default)]
+pub struct SortMarkers {
+    /// Inclusive range of items the sorter is currently working within.
+    pub active: Option<(usize, usize)>,
+    pub pivot: Option<usize>,
+    /// Moving edges of the current partition.
+    pub boundaries: Vec<usize>,
+}
+
+/// A sort that can be advanced one comparison at a time.
+pub trait StepSort<T> {
+    /// Performs one step of the sort, returning `false` once the items are sorted.
+    fn step(&mut self) -> bool;
+
+    fn items(&self) -> &[T];
+
+    /// Comparisons between items made so far.
+    fn comparisons(&self) -> u64;
+
+    /// Swaps of items made so far.
+    fn swaps(&self) -> u64;
+
+    fn markers(&self) -> SortMarkers {
+        SortMarkers::default()
+    }
+
+    /// Fraction of neighbouring items that are out of order, reaching 0.0 once sorted.
+    fn disorder(&self) -> f32
+    where
+        T: Ord,
+    {
+        let items = self.items();
+        if items.len() < 2 {
+            return 0.0;
+        }
+        let out_of_order = items.windows(2).filter(|pair| pair[0] > pair[1]).count();
+        out_of_order as f32 / (items.len() - 1) as f32
+    }
 }
 
 pub struct BubbleSort<T>
@@ -105,93 +507,672 @@ where
     items: Vec<T>,
     did_swap: bool,
     index: usize,
+    unsorted: usize, // Items before the ones earlier passes have bubbled into place
     done: bool,
+    comparisons: u64,
+    swaps: u64,
 }
 
 impl<T: Ord + Clone> BubbleSort<T> {
     pub fn new<I: Iterator<Item = T>>(iter: I) -> Self {
+        let items: Vec<T> = iter.collect();
         BubbleSort {
-            items: iter.collect(),
+            unsorted: items.len(),
+            items,
             did_swap: false,
             index: 0,
             done: false,
+            comparisons: 0,
+            swaps: 0,
         }
     }
 }
 
-impl<T: Ord + Clone> Iterator for BubbleSort<T> {
-    type Item = Vec<T>;
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<T: Ord + Clone> StepSort<T> for BubbleSort<T> {
+    fn step(&mut self) -> bool {
         if self.items.is_empty() || self.done {
-            return None;
+            return false;
         }
 
-        if self.index >= self.items.len() - 1 {
-            if !self.did_swap {
+        if self.index + 1 >= self.unsorted {
+            // Each pass leaves its largest item at the end, so the next can stop before it.
+            if !self.did_swap || self.unsorted <= 2 {
                 self.done = true;
-                return Some(self.items.clone());
+                return true;
             }
+            self.unsorted -= 1;
             self.index = 0;
             self.did_swap = false;
         }
 
+        self.comparisons += 1;
         if self.items[self.index] > self.items[self.index + 1] {
             self.items.swap(self.index, self.index + 1);
+            self.swaps += 1;
             self.did_swap = true;
         }
         self.index += 1;
 
-        Some(self.items.clone())
+        true
+    }
+
+    fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    fn comparisons(&self) -> u64 {
+        self.comparisons
+    }
+
+    fn swaps(&self) -> u64 {
+        self.swaps
+    }
+}
+
+impl<T: Ord + Clone> Iterator for BubbleSort<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.step() {
+            Some(self.items.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// Bubble sort run independently within each band of positions, one comparison per band
+/// in turn so that every band sorts at once.
+pub struct BandedSort<T>
+where
+    T: Ord + Clone,
+{
+    items: Vec<T>,
+    bands: Vec<Vec<usize>>,
+    cursors: Vec<BandCursor>,
+    next: usize, // Band that takes the next step
+    comparisons: u64,
+    swaps: u64,
+}
+
+/// Progress of the bubble sort within one band.
+#[derive(Clone, Default)]
+struct BandCursor {
+    index: usize,
+    did_swap: bool,
+    done: bool,
+}
+
+impl<T: Ord + Clone> BandedSort<T> {
+    pub fn new<I: Iterator<Item = T>>(iter: I, bands: Vec<Vec<usize>>) -> Self {
+        let cursors = vec![BandCursor::default(); bands.len()];
+        BandedSort {
+            items: iter.collect(),
+            bands,
+            cursors,
+            next: 0,
+            comparisons: 0,
+            swaps: 0,
+        }
+    }
+}
+
+impl<T: Ord + Clone> StepSort<T> for BandedSort<T> {
+    fn step(&mut self) -> bool {
+        // Find the next band that still has work to do.
+        let count = self.bands.len();
+        let Some(band) = (0..count)
+            .map(|offset| (self.next + offset) % count)
+            .find(|&b| !self.cursors[b].done)
+        else {
+            return false;
+        };
+        self.next = (band + 1) % count;
+
+        let positions = &self.bands[band];
+        let cursor = &mut self.cursors[band];
+        if cursor.index >= positions.len() - 1 {
+            if !cursor.did_swap {
+                cursor.done = true;
+                return true;
+            }
+            cursor.index = 0;
+            cursor.did_swap = false;
+        }
+
+        let (a, b) = (positions[cursor.index], positions[cursor.index + 1]);
+        self.comparisons += 1;
+        if self.items[a] > self.items[b] {
+            self.items.swap(a, b);
+            self.swaps += 1;
+            cursor.did_swap = true;
+        }
+        cursor.index += 1;
+        true
+    }
+
+    fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    fn comparisons(&self) -> u64 {
+        self.comparisons
+    }
+
+    fn swaps(&self) -> u64 {
+        self.swaps
+    }
+
+    /// Only neighbours within a band count; everything else stays where it is.
+    fn disorder(&self) -> f32
+    where
+        T: Ord,
+    {
+        let (out_of_order, pairs) = self.bands.iter().flat_map(|band| band.windows(2)).fold(
+            (0, 0),
+            |(out_of_order, pairs), pair| {
+                let flipped = self.items[pair[0]] > self.items[pair[1]];
+                (out_of_order + flipped as usize, pairs + 1)
+            },
+        );
+        if pairs == 0 {
+            return 0.0;
+        }
+        out_of_order as f32 / pairs as f32
+    }
+}
+
+/// A Lomuto partition in progress over `lo..=hi`, with the pivot at `hi`.
+struct Partition {
+    lo: usize,
+    hi: usize,
+    store: usize, // Everything before this is smaller than the pivot
+    scan: usize,  // Next item to compare against the pivot
+}
+
+/// Quick sort driven by an explicit stack of ranges so it can pause between comparisons.
+pub struct QuickSort<T>
+where
+    T: Ord + Clone,
+{
+    items: Vec<T>,
+    ranges: Vec<(usize, usize)>,
+    partition: Option<Partition>,
+    comparisons: u64,
+    swaps: u64,
+}
+
+impl<T: Ord + Clone> QuickSort<T> {
+    pub fn new<I: Iterator<Item = T>>(iter: I) -> Self {
+        let items: Vec<T> = iter.collect();
+        let ranges = if items.len() > 1 {
+            vec![(0, items.len() - 1)]
+        } else {
+            Vec::new()
+        };
+        QuickSort {
+            items,
+            ranges,
+            partition: None,
+            comparisons: 0,
+            swaps: 0,
+        }
+    }
+}
+
+impl<T: Ord + Clone> StepSort<T> for QuickSort<T> {
+    fn step(&mut self) -> bool {
+        let Some(partition) = &mut self.partition else {
+            // Pick up the next unsorted range, skipping any that are already trivially sorted.
+            while let Some((lo, hi)) = self.ranges.pop() {
+                if lo < hi {
+                    self.partition = Some(Partition {
+                        lo,
+                        hi,
+                        store: lo,
+                        scan: lo,
+                    });
+                    return true;
+                }
+            }
+            return false;
+        };
+
+        if partition.scan < partition.hi {
+            self.comparisons += 1;
+            if self.items[partition.scan] < self.items[partition.hi] {
+                self.items.swap(partition.store, partition.scan);
+                self.swaps += 1;
+                partition.store += 1;
+            }
+            partition.scan += 1;
+        } else {
+            // Move the pivot between the two halves and queue them up.
+            let Partition { lo, hi, store, .. } = *partition;
+            self.items.swap(store, hi);
+            self.swaps += 1;
+            if store > lo {
+                self.ranges.push((lo, store - 1));
+            }
+            self.ranges.push((store + 1, hi));
+            self.partition = None;
+        }
+        true
+    }
+
+    fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    fn comparisons(&self) -> u64 {
+        self.comparisons
+    }
+
+    fn swaps(&self) -> u64 {
+        self.swaps
+    }
+
+    fn markers(&self) -> SortMarkers {
+        match &self.partition {
+            Some(partition) => SortMarkers {
+                active: Some((partition.lo, partition.hi)),
+                pivot: Some(partition.hi),
+                boundaries: vec![partition.store, partition.scan],
+            },
+            None => SortMarkers::default(),
+        }
+    }
+}
+
+/// Space pauses and resumes; while paused, Left and Right step through the snapshots.
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::Space => {
+            model.paused = !model.paused;
+            model.scrub = None;
+        }
+        Key::Left if model.paused && !model.history.is_empty() => {
+            let latest = model.history.len() - 1;
+            model.scrub = Some(model.scrub.map_or(latest, |i| i.saturating_sub(1)));
+        }
+        Key::Right if model.paused => {
+            if let Some(i) = model.scrub {
+                // Stepping past the newest snapshot goes back to the live grid.
+                model.scrub = (i + 1 < model.history.len()).then_some(i + 1);
+            }
+        }
+        _ => {}
     }
 }
 
 fn update(_app: &App, model: &mut Model, _update: Update) {
-    if model.finished {
+    if model.finished || model.paused {
         return;
     }
 
-    for _ in 0..NUM_SORTS_PER_FRAME {
-        if model.sorter.next().is_none() {
+    let steps = finale_steps(
+        model.steps_per_frame,
+        model.sorter.disorder(),
+        model.finale_slowdown,
+    );
+    for _ in 0..steps {
+        if !model.sorter.step() {
             model.finished = true;
             break;
         }
     }
+
+    model.frame += 1;
+    if model.snapshot_interval > 0
+        && (model.finished || model.frame.is_multiple_of(model.snapshot_interval))
+    {
+        model.take_snapshot();
+    }
+
+    if model.finished {
+        if let Some(montage) = &model.montage {
+            save_montage(montage, &model.history, model.snake);
+        }
+    }
+}
+
+/// Tiles `montage.count` snapshots spread evenly over `history`, left to right and top
+/// to bottom, and saves them.
+fn save_montage(montage: &Montage, history: &VecDeque<Vec<Rgb8>>, snake: bool) {
+    if history.is_empty() {
+        eprintln!("Warning: no snapshots for the montage, set a non-zero --snapshot-interval");
+        return;
+    }
+    let count = montage.count.min(history.len());
+    let cols = montage.cols.min(count);
+    let rows = count.div_ceil(cols);
+    let (tile_width, tile_height) = (PIXEL_GRID_WIDTH as u32, PIXEL_GRID_HEIGHT as u32);
+    let mut image = RgbImage::new(
+        cols as u32 * (tile_width + MONTAGE_GAP) - MONTAGE_GAP,
+        rows as u32 * (tile_height + MONTAGE_GAP) - MONTAGE_GAP,
+    );
+
+    for k in 0..count {
+        let snapshot = if count == 1 {
+            &history[history.len() - 1]
+        } else {
+            &history[k * (history.len() - 1) / (count - 1)]
+        };
+        let left = (k % cols) as u32 * (tile_width + MONTAGE_GAP);
+        let top = (k / cols) as u32 * (tile_height + MONTAGE_GAP);
+        for (idx, color) in snapshot.iter().enumerate() {
+            let cell = if snake { snake_cell(idx) } else { idx };
+            let x = (cell % PIXEL_GRID_WIDTH) as u32;
+            // Grid rows count up from the bottom, image rows down from the top.
+            let y = tile_height - 1 - (cell / PIXEL_GRID_WIDTH) as u32;
+            image.put_pixel(left + x, top + y, Rgb([color.red, color.green, color.blue]));
+        }
+    }
+
+    match image.save(&montage.path) {
+        Ok(()) => println!("Saved montage to {}", montage.path.display()),
+        Err(e) => eprintln!(
+            "Failed to save montage to {}: {}",
+            montage.path.display(),
+            e
+        ),
+    }
+}
+
+/// Scales the per-frame step budget down in proportion to the remaining disorder once it
+/// drops below `threshold`.
+fn finale_steps(steps_per_frame: usize, disorder: f32, threshold: f32) -> usize {
+    if disorder >= threshold {
+        return steps_per_frame;
+    }
+    let speed = (disorder / threshold).max(FINALE_MIN_SPEED);
+    ((steps_per_frame as f32 * speed) as usize).max(1)
 }
 
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
 
     let pixel_size = DISPLAY_WINDOW_WIDTH as f32 / PIXEL_GRID_WIDTH as f32;
+    let items = model.sorter.items();
+    let snapshot = model.scrub.and_then(|i| model.history.get(i));
+    let color_at = |idx: usize| match snapshot {
+        Some(colors) => colors[idx],
+        None => items[idx].color,
+    };
+    let markers = if snapshot.is_some() {
+        SortMarkers::default()
+    } else {
+        model.sorter.markers()
+    };
 
-    // Draw current state
+    // Draw current state, dimming everything outside the range being worked on
     for y in 0..PIXEL_GRID_HEIGHT {
         for x in 0..PIXEL_GRID_WIDTH {
             let idx = y * PIXEL_GRID_WIDTH + x;
-            let color = model.sorter.items[idx].color;
-            let out_min = -(DISPLAY_WINDOW_WIDTH as i32) as f32 / 2.0;
-            let out_max = DISPLAY_WINDOW_WIDTH as f32 / 2.0;
+            let mut color = color_at(idx);
+            if let Some((lo, hi)) = markers.active {
+                if idx < lo || idx > hi {
+                    color = Rgb8::new(color.red / 3, color.green / 3, color.blue / 3);
+                }
+            }
             draw.rect()
-                .x_y(
-                    map_range(x as f32, 0.0, PIXEL_GRID_WIDTH as f32, out_min, out_max),
-                    map_range(y as f32, 0.0, PIXEL_GRID_HEIGHT as f32, out_min, out_max),
-                )
+                .xy(pixel_position(idx, model.snake))
                 .w_h(pixel_size, pixel_size)
                 .color(color);
         }
     }
 
-    watermark(&draw);
+    // Mark the partition boundaries and the pivot on top
+    for &idx in &markers.boundaries {
+        if idx < items.len() {
+            draw.rect()
+                .xy(pixel_position(idx, model.snake))
+                .w_h(pixel_size * 3.0, pixel_size * 3.0)
+                .no_fill()
+                .stroke(WHITE)
+                .stroke_weight(1.0);
+        }
+    }
+    if let Some(idx) = markers.pivot {
+        draw.rect()
+            .xy(pixel_position(idx, model.snake))
+            .w_h(pixel_size * 4.0, pixel_size * 4.0)
+            .color(WHITE);
+    }
+
+    if model.histogram {
+        // The whole grid is only ever permuted, so its histogram never changes; the bottom
+        // band's does, converging on the target's as the sort settles.
+        let current = brightness_histogram((0..HISTOGRAM_ROWS * PIXEL_GRID_WIDTH).map(color_at));
+        draw_histogram(&draw, app.window_rect(), &current, &model.target_histogram);
+    }
+
+    if let Some(i) = model.scrub {
+        draw.text(&format!("snapshot {}/{}", i + 1, model.history.len()))
+            .color(WHITE)
+            .font_size(18)
+            .xy(corner_point(
+                app.window_rect(),
+                Corner::TopLeft,
+                vec2(100.0, 30.0),
+            ));
+    }
+
+    if model.show_entropy && model.scrub.is_none() {
+        draw_entropy(model, &draw, app.window_rect());
+    }
+
+    draw.text(&format!(
+        "{} comparisons\n{} swaps",
+        model.sorter.comparisons(),
+        model.sorter.swaps()
+    ))
+    .color(WHITE)
+    .font_size(18)
+    .w(300.0)
+    .right_justify()
+    .xy(corner_point(
+        app.window_rect(),
+        Corner::BottomRight,
+        vec2(170.0, 40.0),
+    ));
+
+    // The scramble behind it can be any color, so white rather than a contrast pick.
+    draw_watermark(
+        &draw,
+        app.window_rect(),
+        "1.31",
+        Corner::BottomLeft,
+        Some(rgba(1.0, 1.0, 1.0, 1.0)),
+        BLACK.into_format(),
+    );
     draw.to_frame(app, &frame).unwrap();
 }
 
-fn watermark(draw: &Draw) {
-    draw.text("1.31")
-        .color(WHITE)
-        .font_size(24)
-        .align_text_bottom()
+/// Bars for `current` with `target` outlined over them, in the top-right corner.
+fn draw_histogram(draw: &Draw, rect: Rect, current: &[u32], target: &[u32]) {
+    let top_right = corner_point(rect, Corner::TopRight, vec2(20.0, 20.0));
+    let (right, top) = (top_right.x, top_right.y);
+    let left = right - HISTOGRAM_WIDTH;
+    let bottom = top - HISTOGRAM_HEIGHT;
+
+    draw.rect()
+        .x_y(
+            left + HISTOGRAM_WIDTH / 2.0,
+            bottom + HISTOGRAM_HEIGHT / 2.0,
+        )
+        .w_h(HISTOGRAM_WIDTH + 8.0, HISTOGRAM_HEIGHT + 8.0)
+        .color(rgba(0.0, 0.0, 0.0, 0.7));
+
+    let max = current
+        .iter()
+        .chain(target)
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+    let bar_width = HISTOGRAM_WIDTH / current.len() as f32;
+    for (i, (&count, &goal)) in current.iter().zip(target).enumerate() {
+        let x = left + (i as f32 + 0.5) * bar_width;
+        let shade = (i as f32 + 0.5) / current.len() as f32;
+
+        let h = count as f32 / max * HISTOGRAM_HEIGHT;
+        draw.rect()
+            .x_y(x, bottom + h / 2.0)
+            .w_h(bar_width - 1.0, h)
+            .color(rgb(shade, shade, shade));
+
+        let h = goal as f32 / max * HISTOGRAM_HEIGHT;
+        draw.rect()
+            .x_y(x, bottom + h / 2.0)
+            .w_h(bar_width - 1.0, h)
+            .no_fill()
+            .stroke(ORANGE)
+            .stroke_weight(1.0);
+    }
+}
+
+/// The current displacement as a share of where the sort started, in the top left.
+fn draw_entropy(model: &Model, draw: &Draw, rect: Rect) {
+    let current = displacement(model.sorter.items(), &model.target_position);
+    let fraction = if model.initial_displacement > 0 {
+        current as f32 / model.initial_displacement as f32
+    } else {
+        0.0
+    };
+    let top_left = corner_point(rect, Corner::TopLeft, vec2(20.0, 20.0));
+    let (left, top) = (top_left.x, top_left.y);
+
+    draw.rect()
         .x_y(
-            -(DISPLAY_WINDOW_WIDTH as f32) / 2.0 + 40.0,
-            -(DISPLAY_WINDOW_HEIGHT as f32) / 2.0 + 110.0,
+            left + ENTROPY_BAR_WIDTH / 2.0,
+            top - ENTROPY_BAR_HEIGHT / 2.0,
+        )
+        .w_h(ENTROPY_BAR_WIDTH, ENTROPY_BAR_HEIGHT)
+        .color(rgba(0.0, 0.0, 0.0, 0.6))
+        .stroke(WHITE)
+        .stroke_weight(1.0);
+    // The bar shrinks towards the left edge as the disorder goes.
+    let width = ENTROPY_BAR_WIDTH * fraction.min(1.0);
+    draw.rect()
+        .x_y(left + width / 2.0, top - ENTROPY_BAR_HEIGHT / 2.0)
+        .w_h(width, ENTROPY_BAR_HEIGHT)
+        .color(WHITE);
+
+    draw.text(&format!(
+        "displacement {} ({:.1}%)",
+        current,
+        fraction * 100.0
+    ))
+    .color(WHITE)
+    .font_size(14)
+    .w(ENTROPY_BAR_WIDTH + 100.0)
+    .left_justify()
+    .x_y(left + (ENTROPY_BAR_WIDTH + 100.0) / 2.0, top - 24.0);
+}
+
+/// Center of the on-screen cell for the item at `idx`, following the serpentine if `snake`.
+fn pixel_position(idx: usize, snake: bool) -> Point2 {
+    let cell = if snake { snake_cell(idx) } else { idx };
+    let x = cell % PIXEL_GRID_WIDTH;
+    let y = cell / PIXEL_GRID_WIDTH;
+    let out_min = -(DISPLAY_WINDOW_WIDTH as i32) as f32 / 2.0;
+    let out_max = DISPLAY_WINDOW_WIDTH as f32 / 2.0;
+    pt2(
+        map_range(x as f32, 0.0, PIXEL_GRID_WIDTH as f32, out_min, out_max),
+        map_range(y as f32, 0.0, PIXEL_GRID_HEIGHT as f32, out_min, out_max),
+    )
+}
+
+/// Row-major grid cell of the `idx`th step of a serpentine that runs left to right along
+/// even rows and back along odd ones. Mirroring a row twice undoes it, so this also maps
+/// a cell back to its step.
+fn snake_cell(idx: usize) -> usize {
+    let row = idx / PIXEL_GRID_WIDTH;
+    let col = idx % PIXEL_GRID_WIDTH;
+    if row % 2 == 1 {
+        row * PIXEL_GRID_WIDTH + PIXEL_GRID_WIDTH - 1 - col
+    } else {
+        idx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thinning_keeps_the_first_snapshot_and_every_other_one() {
+        let mut history: VecDeque<usize> = (0..=MAX_SNAPSHOTS).collect();
+        thin_history(&mut history);
+        assert_eq!(history.len(), MAX_SNAPSHOTS / 2 + 1);
+        assert_eq!(history.front(), Some(&0));
+        assert!(history.iter().all(|frame| frame % 2 == 0));
+    }
+
+    #[test]
+    fn bubble_sort_counts_every_pair_of_a_reversed_slice() {
+        let n = 20;
+        let mut sort = BubbleSort::new((0..n).rev());
+        while sort.step() {}
+
+        assert_eq!(sort.items(), (0..n).collect::<Vec<_>>());
+        // Reversed, every pair is out of order, and each one is compared once and swapped.
+        let pairs = (n * (n - 1) / 2) as u64;
+        assert_eq!(sort.comparisons(), pairs);
+        assert_eq!(sort.swaps(), pairs);
+    }
+
+    #[test]
+    fn bubble_sort_stops_after_one_pass_when_sorted() {
+        let mut sort = BubbleSort::new(0..10);
+        while sort.step() {}
+        assert_eq!(sort.comparisons(), 9);
+        assert_eq!(sort.swaps(), 0);
+    }
+
+    #[test]
+    fn floyd_steinberg_spreads_a_ramp_across_both_levels() {
+        // A dark-to-light ramp from left to right, the same in every row.
+        let shade = |x: usize| (x * 255 / (PIXEL_GRID_WIDTH - 1)) as u8;
+        let mut colors: Vec<Rgb8> = (0..PIXEL_GRID_WIDTH * PIXEL_GRID_HEIGHT)
+            .map(|i| {
+                let v = shade(i % PIXEL_GRID_WIDTH);
+                Rgb8::new(v, v, v)
+            })
+            .collect();
+        floyd_steinberg(&mut colors, 2);
+        let dithered = &colors;
+
+        assert!(dithered.iter().all(|c| c.red == 0 || c.red == 255));
+
+        // Thresholding would give every column a single level, with a hard edge halfway.
+        let column =
+            |x: usize| (0..PIXEL_GRID_HEIGHT).map(move |y| dithered[y * PIXEL_GRID_WIDTH + x]);
+        let mixed = (0..PIXEL_GRID_WIDTH)
+            .filter(|&x| column(x).any(|c| c.red == 0) && column(x).any(|c| c.red == 255))
+            .count();
+        assert!(
+            mixed > PIXEL_GRID_WIDTH / 2,
+            "only {} columns mix levels",
+            mixed
         );
+
+        // Averaged over a band of columns, the dots come out as the shade they replace.
+        let column_mean =
+            |x: usize| column(x).map(|c| c.red as f32).sum::<f32>() / PIXEL_GRID_HEIGHT as f32;
+        const BAND: usize = 10;
+        for band in 0..PIXEL_GRID_WIDTH / BAND {
+            let xs = band * BAND..(band + 1) * BAND;
+            let input = xs.clone().map(|x| shade(x) as f32).sum::<f32>() / BAND as f32;
+            let output = xs.map(column_mean).sum::<f32>() / BAND as f32;
+            assert!(
+                (input - output).abs() < 16.0,
+                "band {} averages {} for a shade of {}",
+                band,
+                output,
+                input
+            );
+        }
+    }
 }
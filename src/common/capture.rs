@@ -0,0 +1,84 @@
+//! Saving a still of any sketch with the S key.
+
+use nannou::prelude::*;
+use nannou::winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+use std::path::{Path, PathBuf};
+
+const CAPTURE_DIR: &str = "captures";
+
+/// Makes pressing S save the window's next frame to `captures/`. It hooks the raw window
+/// events, so the sketch's own `key_pressed` handler still sees every key.
+pub fn attach_capture<M: 'static>(builder: window::Builder<'_, M>) -> window::Builder<'_, M> {
+    builder.raw_event(capture_on_s)
+}
+
+/// A model with more to save than the frame, such as what it takes to resume from it.
+pub trait CaptureState {
+    /// Writes the state alongside `image`, the path the frame is being captured to.
+    fn save_state(&self, image: &Path);
+}
+
+/// Like `attach_capture`, but each capture also saves the model's state next to the image.
+pub fn attach_capture_with_state<M: CaptureState + 'static>(
+    builder: window::Builder<'_, M>,
+) -> window::Builder<'_, M> {
+    builder.raw_event(capture_state_on_s)
+}
+
+fn capture_on_s<M>(app: &App, _model: &mut M, event: &WindowEvent) {
+    capture_if_s(app, event);
+}
+
+fn capture_state_on_s<M: CaptureState>(app: &App, model: &mut M, event: &WindowEvent) {
+    if let Some(image) = capture_if_s(app, event) {
+        model.save_state(&image);
+    }
+}
+
+/// Captures the next frame if `event` is S being pressed, returning the image's path.
+fn capture_if_s(app: &App, event: &WindowEvent) -> Option<PathBuf> {
+    let WindowEvent::KeyboardInput {
+        input:
+            KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(VirtualKeyCode::S),
+                ..
+            },
+        ..
+    } = event
+    else {
+        return None;
+    };
+
+    if let Err(e) = std::fs::create_dir_all(CAPTURE_DIR) {
+        eprintln!("Failed to create {} ({})", CAPTURE_DIR, e);
+        return None;
+    }
+    let path = capture_path(Path::new(CAPTURE_DIR));
+    // Written in the background once the frame is drawn, so the animation carries on.
+    app.main_window().capture_frame(&path);
+    println!("Saved {}", path.display());
+    Some(path)
+}
+
+/// A file in `dir` named after the running example and the current UTC time down to the
+/// millisecond, so quick repeated presses don't overwrite each other, e.g.
+/// `day20_2025-01-20_143501-042.png`.
+fn capture_path(dir: &Path) -> PathBuf {
+    let day = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_stem()?.to_str().map(str::to_owned))
+        .unwrap_or_else(|| "sketch".to_string());
+    let now = time::OffsetDateTime::now_utc();
+    dir.join(format!(
+        "day{}_{:04}-{:02}-{:02}_{:02}{:02}{:02}-{:03}.png",
+        day,
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second(),
+        now.millisecond(),
+    ))
+}
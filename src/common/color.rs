@@ -0,0 +1,17 @@
+//! Parsing colors from the command line.
+
+use nannou::prelude::*;
+
+/// Parses a `#rrggbb` hex color, with or without the `#`. Meant as a clap `value_parser`.
+pub fn parse_hex_color(s: &str) -> Result<Rgb, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("expected a color like #87a0b4, got {}", s));
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16)
+            .map(|value| value as f32 / 255.0)
+            .map_err(|e| e.to_string())
+    };
+    Ok(rgb(channel(0)?, channel(2)?, channel(4)?))
+}
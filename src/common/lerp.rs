@@ -0,0 +1,66 @@
+//! Linear interpolation between values and between points. Nothing is clamped, so `t`
+//! outside 0 to 1 extrapolates past either end.
+
+use nannou::prelude::*;
+
+/// The value `t` of the way from `a` to `b`.
+#[inline]
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// The point `t` of the way from `a` to `b`.
+#[inline]
+pub fn lerp_point(a: Point2, b: Point2, t: f32) -> Point2 {
+    a + (b - a) * t
+}
+
+/// How far `v` is from `a` towards `b`, the inverse of `lerp`. An empty range gives 0
+/// rather than dividing by zero.
+#[inline]
+pub fn inv_lerp(a: f32, b: f32, v: f32) -> f32 {
+    if a == b {
+        0.0
+    } else {
+        (v - a) / (b - a)
+    }
+}
+
+/// Maps `v` from the range `from` to the same place in the range `to`.
+#[inline]
+pub fn remap(v: f32, from: (f32, f32), to: (f32, f32)) -> f32 {
+    lerp(to.0, to.1, inv_lerp(from.0, from.1, v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_extrapolates_past_either_end() {
+        assert_eq!(lerp(10.0, 20.0, -0.5), 5.0);
+        assert_eq!(lerp(10.0, 20.0, 1.5), 25.0);
+    }
+
+    #[test]
+    fn lerp_point_extrapolates_past_either_end() {
+        let (a, b) = (pt2(0.0, 10.0), pt2(10.0, -10.0));
+        assert_eq!(lerp_point(a, b, -1.0), pt2(-10.0, 30.0));
+        assert_eq!(lerp_point(a, b, 2.0), pt2(20.0, -30.0));
+    }
+
+    #[test]
+    fn remap_extrapolates_past_either_end() {
+        assert_eq!(remap(-5.0, (0.0, 10.0), (100.0, 200.0)), 50.0);
+        assert_eq!(remap(15.0, (0.0, 10.0), (100.0, 200.0)), 250.0);
+        // A reversed target range extrapolates the other way.
+        assert_eq!(remap(15.0, (0.0, 10.0), (200.0, 100.0)), 50.0);
+    }
+
+    #[test]
+    fn inv_lerp_of_an_empty_range_is_zero() {
+        assert_eq!(inv_lerp(3.0, 3.0, 3.0), 0.0);
+        assert_eq!(inv_lerp(3.0, 3.0, 7.0), 0.0);
+        assert_eq!(remap(7.0, (3.0, 3.0), (100.0, 200.0)), 100.0);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod capture;
+pub mod color;
+pub mod lerp;
+pub mod pulse;
+pub mod record;
+pub mod svg;
+pub mod watermark;
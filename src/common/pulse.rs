@@ -0,0 +1,52 @@
+//! A throb that swells a sketch's pattern in time, like a drum hit.
+
+/// Command-line options for the pulse, flattened into a sketch's own `Args`.
+#[derive(clap::Args, Debug)]
+pub struct PulseArgs {
+    /// Throb the pattern this many times per second
+    #[arg(long)]
+    pub pulse: Option<f32>,
+
+    /// Fraction of each pulse spent swelling up
+    #[arg(long, default_value_t = 0.1)]
+    pub pulse_attack: f32,
+
+    /// How quickly each pulse dies away after its peak
+    #[arg(long, default_value_t = 5.0)]
+    pub pulse_decay: f32,
+
+    /// How much bigger the pattern gets at the peak of a pulse
+    #[arg(long, default_value_t = 0.15)]
+    pub pulse_depth: f32,
+}
+
+/// A repeating attack/decay envelope.
+pub struct Pulse {
+    frequency: f32,
+    attack: f32, // Fraction of the period spent rising
+    decay: f32,  // Exponential falloff rate after the peak
+    depth: f32,
+}
+
+impl Pulse {
+    /// The pulse `args` ask for, if any.
+    pub fn from_args(args: &PulseArgs) -> Option<Self> {
+        args.pulse.map(|frequency| Pulse {
+            frequency,
+            attack: args.pulse_attack.clamp(0.0, 1.0),
+            decay: args.pulse_decay,
+            depth: args.pulse_depth,
+        })
+    }
+
+    /// Scale factor for the pattern at `time`, 1.0 at rest.
+    pub fn scale(&self, time: f32) -> f32 {
+        let phase = (time * self.frequency).fract();
+        let envelope = if phase < self.attack {
+            phase / self.attack
+        } else {
+            (-self.decay * (phase - self.attack)).exp()
+        };
+        1.0 + self.depth * envelope
+    }
+}
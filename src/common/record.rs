@@ -0,0 +1,84 @@
+//! Recording a sketch to numbered PNGs at a fixed frame rate.
+
+use nannou::prelude::*;
+use std::path::PathBuf;
+
+const RECORD_FPS: f32 = 60.0; // Logical frame rate of a recording, whatever the real one
+
+/// Command-line options for recording, flattened into a sketch's own `Args`.
+#[derive(clap::Args, Debug)]
+pub struct RecordArgs {
+    /// Record this many frames to numbered PNGs at a fixed time step, then exit
+    #[arg(long)]
+    pub record_frames: Option<u64>,
+
+    /// Directory `--record-frames` writes into
+    #[arg(long, default_value = "recording")]
+    pub record_dir: PathBuf,
+}
+
+/// Writes a window's frames to `frame_00000.png`, `frame_00001.png`, ... and quits once
+/// enough are written. While recording, a sketch should take its time from
+/// `Recorder::time`, which steps a fixed `dt` per frame however long each takes to draw,
+/// so the frames play back at an even speed.
+pub struct Recorder {
+    dir: PathBuf,
+    frames: u64,
+    frame: u64, // Index of the next frame to capture
+    window: WindowId,
+}
+
+impl Recorder {
+    /// The recorder `args` ask for, if any, creating its directory.
+    pub fn from_args(args: &RecordArgs, window: WindowId) -> Option<Self> {
+        let frames = args.record_frames?;
+        match std::fs::create_dir_all(&args.record_dir) {
+            Ok(()) => Some(Recorder {
+                dir: args.record_dir.clone(),
+                frames,
+                frame: 0,
+                window,
+            }),
+            Err(e) => {
+                eprintln!(
+                    "Warning: couldn't create {} ({}), not recording",
+                    args.record_dir.display(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Seconds of logical time between recorded frames.
+    pub fn dt(&self) -> f32 {
+        1.0 / RECORD_FPS
+    }
+
+    /// Logical time of the frame about to be drawn.
+    pub fn time(&self) -> f32 {
+        self.frame as f32 * self.dt()
+    }
+
+    /// Call at the end of `update`. Queues the frame `view` is about to draw, or quits once
+    /// every frame has been queued.
+    pub fn capture(&mut self, app: &App) {
+        if self.frame >= self.frames {
+            app.quit();
+            return;
+        }
+        if let Some(window) = app.window(self.window) {
+            window.capture_frame(self.dir.join(format!("frame_{:05}.png", self.frame)));
+        }
+        self.frame += 1;
+    }
+
+    /// Call from the app's `exit` so frames still being written aren't lost.
+    pub fn finish(&self, app: &App) {
+        if let Some(window) = app.window(self.window) {
+            if let Err(e) = window.await_capture_frame_jobs() {
+                eprintln!("Failed to write recorded frames: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Writing line drawings out as SVG, for sketches that are vector art to begin with.
+
+use nannou::prelude::*;
+use std::io;
+use std::path::Path;
+
+/// An open line through `points`, in nannou's coordinates.
+pub struct Polyline {
+    pub points: Vec<Point2>,
+    pub stroke: Srgb<u8>,
+    pub stroke_width: f32,
+}
+
+/// Writes the lines as a `width` by `height` document over a solid background. nannou puts
+/// the origin in the middle with y up, so points are moved to SVG's top-left origin with y
+/// down on the way out.
+pub fn write_svg(
+    path: &Path,
+    width: u32,
+    height: u32,
+    background: Srgb<u8>,
+    lines: &[Polyline],
+) -> io::Result<()> {
+    std::fs::write(path, svg_document(width, height, background, lines))
+}
+
+fn svg_document(width: u32, height: u32, background: Srgb<u8>, lines: &[Polyline]) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+    svg.push_str(&format!(
+        "  <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        hex(background)
+    ));
+    for line in lines {
+        let points: Vec<String> = line
+            .points
+            .iter()
+            .map(|p| {
+                format!(
+                    "{:.2},{:.2}",
+                    p.x + width as f32 / 2.0,
+                    height as f32 / 2.0 - p.y
+                )
+            })
+            .collect();
+        svg.push_str(&format!(
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" \
+             stroke-linejoin=\"round\" stroke-linecap=\"round\"/>\n",
+            points.join(" "),
+            hex(line.stroke),
+            line.stroke_width
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn hex(color: Srgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_move_to_a_top_left_origin_with_y_down() {
+        let line = Polyline {
+            points: vec![pt2(-200.0, 100.0), pt2(0.0, 0.0), pt2(200.0, -100.0)],
+            stroke: BLACK,
+            stroke_width: 1.0,
+        };
+        let svg = svg_document(400, 200, WHITE, &[line]);
+        assert!(
+            svg.contains("points=\"0.00,0.00 200.00,100.00 400.00,200.00\""),
+            "{}",
+            svg
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! The day label each sketch stamps in a corner of its window, and the corner placement
+//! the sketches' other overlays share.
+
+use nannou::prelude::*;
+
+const FONT_SIZE: u32 = 24;
+const BOX_WIDTH: f32 = 200.0; // Text box the label is justified within
+const BOX_HEIGHT: f32 = 200.0;
+const MARGIN_X: f32 = 20.0; // Gap between the label and the side of the window
+const MARGIN_Y: f32 = 10.0; // Gap between the label and the top or bottom of the window
+
+/// A corner of the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    fn is_left(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::BottomLeft)
+    }
+
+    fn is_bottom(self) -> bool {
+        matches!(self, Corner::BottomLeft | Corner::BottomRight)
+    }
+}
+
+/// The point `inset` in from `corner` of `rect`, measured toward the middle on both axes.
+pub fn corner_point(rect: Rect, corner: Corner, inset: Vec2) -> Point2 {
+    let x = if corner.is_left() {
+        rect.left() + inset.x
+    } else {
+        rect.right() - inset.x
+    };
+    let y = if corner.is_bottom() {
+        rect.bottom() + inset.y
+    } else {
+        rect.top() - inset.y
+    };
+    pt2(x, y)
+}
+
+/// Draws `label` tucked into `corner` of `rect`, justified toward the nearer side so it
+/// stays put whatever its length and whatever the window's size. Without a `color` it
+/// picks one that reads on `background`.
+pub fn draw_watermark(
+    draw: &Draw,
+    rect: Rect,
+    label: &str,
+    corner: Corner,
+    color: Option<Rgba>,
+    background: Rgb,
+) {
+    let left = corner.is_left();
+    let bottom = corner.is_bottom();
+    let inset = vec2(MARGIN_X + BOX_WIDTH / 2.0, MARGIN_Y + BOX_HEIGHT / 2.0);
+
+    let text = draw
+        .text(label)
+        .color(color.unwrap_or_else(|| readable_color(background)))
+        .font_size(FONT_SIZE)
+        .w_h(BOX_WIDTH, BOX_HEIGHT)
+        .xy(corner_point(rect, corner, inset));
+    let text = if left {
+        text.left_justify()
+    } else {
+        text.right_justify()
+    };
+    if bottom {
+        text.align_text_bottom();
+    } else {
+        text.align_text_top();
+    }
+}
+
+/// A watermark color that stands out on `background`: translucent black on light
+/// backgrounds, translucent white on dark ones.
+pub fn readable_color(background: Rgb) -> Rgba {
+    let luminance = 0.2126 * background.red + 0.7152 * background.green + 0.0722 * background.blue;
+    if luminance > 0.5 {
+        rgba(0.0, 0.0, 0.0, 0.5)
+    } else {
+        rgba(1.0, 1.0, 1.0, 0.5)
+    }
+}
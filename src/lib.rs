@@ -0,0 +1,3 @@
+//! Pieces shared between the sketches in `days/`.
+
+pub mod common;